@@ -1,10 +1,35 @@
 use proc_macro::TokenStream;
 
 use proc_macro2::Ident;
-use quote::{quote, ToTokens};
-use syn::{parse_macro_input, Data, DeriveInput, Fields, Meta, Type};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
+use syn::spanned::Spanned;
+use syn::{
+    parenthesized, parse_macro_input, parse_quote, token, Attribute, Data, DeriveInput, Expr,
+    Fields, GenericArgument, GenericParam, Generics, LitStr, Member, Meta, Path, PathArguments,
+    Token, Type, Visibility,
+};
 
-static OPTION_MODIFIER: [&str; 4] = ["<", ">", "(", ")"];
+/// Paths recognized as `Option`.
+static OPTION_PATHS: &[&[&str]] = &[
+    &["Option"],
+    &["std", "option", "Option"],
+    &["core", "option", "Option"],
+];
+
+/// Collections taking their item type as the first type argument.
+static SEQUENCE_COLLECTIONS: [&str; 6] = [
+    "Vec",
+    "VecDeque",
+    "LinkedList",
+    "BinaryHeap",
+    "HashSet",
+    "BTreeSet",
+];
+
+/// Collections taking `(K, V)` pairs as items.
+static MAP_COLLECTIONS: [&str; 2] = ["HashMap", "BTreeMap"];
 
 macro_rules! compile_error {
     ($span: expr, $($arg: expr)*) => {
@@ -14,6 +39,14 @@ macro_rules! compile_error {
     };
 }
 
+macro_rules! ident_token_str {
+    ($ident: expr) => {
+        format!("\"{}\"", $ident.to_string().as_str())
+            .parse::<proc_macro2::TokenStream>()
+            .unwrap()
+    };
+}
+
 macro_rules! compile_span_error {
     ($span: expr, $arg: expr) => {
         syn::Error::new_spanned($span, $arg)
@@ -27,6 +60,300 @@ macro_rules! compile_span_error {
     };
 }
 
+/// Options set on the struct itself through `#[builder(...)]`.
+#[derive(Default)]
+struct ContainerOpts {
+    /// `#[builder(typestate)]`, track set fields in the builder type instead of at runtime.
+    typestate: bool,
+    /// `#[builder(setter(prefix = "..."))]`, prepended to setter names derived from field names.
+    setter_prefix: Option<String>,
+    /// `#[builder(getter(prefix = "..."))]`, prepended to getter names, `get_` by default.
+    getter_prefix: Option<String>,
+    /// `#[builder(pattern = "...")]`.
+    pattern: Option<Pattern>,
+    /// `#[builder(validate = "...")]`, checks the built value before `build()` returns it.
+    validate: Option<Path>,
+    /// `#[builder(derive(Deserialize))]`, make the builder deserializable with serde.
+    deserialize: bool,
+    /// Other traits in `#[builder(derive(...))]`, derived on the builder as is.
+    derives: Vec<Path>,
+    /// `#[builder(attr(...))]`, attributes put on the builder.
+    attrs: Vec<proc_macro2::TokenStream>,
+    /// `#[builder(vis = "...")]`, visibility of the builder and its error, the visibility of the
+    /// type by default.
+    vis: Option<Visibility>,
+    /// `#[builder(name = "...")]`, name of the builder instead of `NameBuilder`.
+    name: Option<Ident>,
+    /// `#[builder(to_builder)]`, generate `to_builder()` and `From<T>` for the builder.
+    to_builder: bool,
+    /// `#[builder(no_std)]`, use `alloc` instead of `std` in the generated code.
+    no_std: bool,
+    /// `#[builder(build_fn(...))]`.
+    build_fn: BuildFnOpts,
+    /// `#[builder(from_env)]` or `#[builder(from_env(separator = "..."))]`, generate
+    /// `from_env()` splitting `each` variables on the given separator.
+    from_env: Option<String>,
+}
+
+/// Options of the `build()` method.
+#[derive(Default)]
+struct BuildFnOpts {
+    /// `name = "..."`, method name instead of `build`.
+    name: Option<Ident>,
+    /// `error = "..."`, error type implementing `From` for the generated error.
+    error: Option<Type>,
+    /// `private`, do not make the method `pub`.
+    private: bool,
+    /// `async`, make the method `async`.
+    is_async: bool,
+    /// `with = "..."`, function constructing the value from the field values, in declaration
+    /// order. It returns `Result<T, E>` where `E` converts into the error type of the method,
+    /// and is awaited with `async`.
+    with: Option<Path>,
+}
+
+impl ContainerOpts {
+    /// Derives and attributes of the builder struct, `clone` adds `Clone` if not derived
+    /// already.
+    fn builder_attrs(&self, clone: bool) -> proc_macro2::TokenStream {
+        let mut derives: Vec<proc_macro2::TokenStream> =
+            self.derives.iter().map(ToTokens::to_token_stream).collect();
+        let has_clone = self
+            .derives
+            .iter()
+            .any(|v| v.segments.last().is_some_and(|s| s.ident == "Clone"));
+        if clone && !has_clone {
+            derives.push(quote!(::core::clone::Clone));
+        }
+        if self.deserialize {
+            derives.push(quote!(::serde::Deserialize));
+        }
+        let attrs = &self.attrs;
+        quote!(
+            #[derive(#(#derives),*)]
+            #(#[#attrs])*
+        )
+    }
+}
+
+/// How setters and `build()` take the builder.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum Pattern {
+    /// Setters and `build()` consume the builder, fields are moved out without cloning.
+    Owned,
+    /// Setters take `&mut self` and return `&mut Self`, `build()` clones every field.
+    #[default]
+    Mutable,
+    /// Setters take `&self` and return an updated copy of the builder.
+    Immutable,
+}
+
+impl Pattern {
+    /// Receiver and return type of setters, and the statement binding the builder to update as
+    /// `builder`.
+    fn setter(
+        self,
+    ) -> (
+        proc_macro2::TokenStream,
+        proc_macro2::TokenStream,
+        proc_macro2::TokenStream,
+    ) {
+        match self {
+            Self::Owned => (quote!(self), quote!(Self), quote!(let mut builder = self;)),
+            Self::Mutable => (
+                quote!(&mut self),
+                quote!(&mut Self),
+                quote!(let builder = self;),
+            ),
+            Self::Immutable => (
+                quote!(&self),
+                quote!(Self),
+                quote!(let mut builder = ::core::clone::Clone::clone(self);),
+            ),
+        }
+    }
+
+    fn build_receiver(self) -> proc_macro2::TokenStream {
+        match self {
+            Self::Owned => quote!(self),
+            Self::Mutable => quote!(&mut self),
+            Self::Immutable => quote!(&self),
+        }
+    }
+
+    /// Read a stored field of `builder` in `build()`.
+    fn take(self, i: &Ident) -> proc_macro2::TokenStream {
+        match self {
+            Self::Owned => quote!(builder.#i),
+            Self::Mutable | Self::Immutable => quote!(::core::clone::Clone::clone(&builder.#i)),
+        }
+    }
+}
+
+/// How the builder fills a field.
+#[allow(clippy::large_enum_variant)]
+enum FieldKind {
+    /// Must be set before `build()`.
+    Required,
+    /// `Option<T>` field, the setter takes the inner `T`.
+    Optional(Type),
+    /// Collection field with `#[builder(each = "...")]`, the setter extends it with one item at
+    /// a time.
+    Each {
+        /// `None` with `#[builder(setter(skip))]`.
+        setter: Option<Ident>,
+        item: EachItem,
+        into: bool,
+        merge: MergeMode,
+    },
+    /// `#[builder(sub_builder)]`, the field type has its own builder, which is stored instead
+    /// and built along with this one.
+    SubBuilder(Type),
+}
+
+/// How `merge()` combines an `each` collection set in both builders.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum MergeMode {
+    /// Extend the collection with the items of the other builder.
+    #[default]
+    Append,
+    /// Use the collection of the other builder.
+    Replace,
+}
+
+/// Item accepted by an `each` setter.
+#[allow(clippy::large_enum_variant)]
+enum EachItem {
+    Single(Type),
+    /// Key and value of a map.
+    Pair(Type, Type),
+}
+
+impl EachItem {
+    /// The item type, `(K, V)` for maps.
+    fn to_type_tokens(&self) -> proc_macro2::TokenStream {
+        match self {
+            Self::Single(v) => quote!(#v),
+            Self::Pair(k, v) => quote!((#k, #v)),
+        }
+    }
+}
+
+/// Options set on a field through `#[builder(...)]`.
+#[derive(Default)]
+struct FieldOpts {
+    /// `#[builder(each = "...")]` or `#[builder(each(name = "...", bulk = "...", into))]`.
+    each: Option<EachOpts>,
+    /// `#[builder(default)]` or `#[builder(default = ...)]`, value used when the field is unset.
+    default: Option<proc_macro2::TokenStream>,
+    /// `#[builder(setter(into))]`, let the setter take `impl Into<T>`.
+    setter_into: bool,
+    /// `#[builder(setter(name = "..."))]`, setter name used instead of the field name.
+    setter_name: Option<Ident>,
+    /// `#[builder(setter(skip))]`, generate no setter so the field always uses its default.
+    setter_skip: bool,
+    /// `#[builder(validate = "...")]`, checks the field value in `build()`.
+    validate: Option<Path>,
+    /// `#[builder(name = "...")]`, name of a tuple field in the builder.
+    name: Option<Ident>,
+    /// `#[builder(merge = "...")]`, how `merge()` combines an `each` collection.
+    merge: Option<(LitStr, MergeMode)>,
+    /// `#[builder(env = "...")]`, `#[builder(env(name = "...", separator = "..."))]` or
+    /// `#[builder(env(skip))]`.
+    env: Option<EnvOpts>,
+    /// `#[builder(sub_builder)]`, build the field with the builder of its type.
+    sub_builder: bool,
+}
+
+struct EnvOpts {
+    /// Environment variable read instead of `PREFIX_FIELD`, used as is.
+    name: Option<LitStr>,
+    /// Separator of `each` items, overriding the one given to `from_env`.
+    separator: Option<String>,
+    /// Leave the field out of `from_env()`, e.g. when its type does not implement `FromStr`.
+    skip: bool,
+}
+
+struct EachOpts {
+    /// Name of the setter taking one item at a time.
+    name: Ident,
+    /// Name of the setter taking the whole collection.
+    bulk: Option<Ident>,
+    /// Let the setter take `impl Into<Item>`.
+    into: bool,
+}
+
+/// A type built by a generated builder: the struct itself or one enum variant.
+struct Target<'a> {
+    /// The type deriving `Builder`.
+    ident: &'a Ident,
+    generics: &'a Generics,
+    /// Path used to construct the value, `Name` or `Name::Variant`.
+    path: proc_macro2::TokenStream,
+    /// Associated function on `ident` creating the builder.
+    constructor: Ident,
+    builder_ident: Ident,
+    /// Visibility of the builder and its error type.
+    vis: &'a Visibility,
+}
+
+struct BuilderField<'a> {
+    /// Name of the field in the builder, `_0`, `_1`, ... for tuple fields unless renamed.
+    ident: Ident,
+    /// The field in the built value.
+    member: Member,
+    ty: &'a Type,
+    kind: FieldKind,
+    /// Expression to use instead of reporting the field as missing.
+    default: Option<proc_macro2::TokenStream>,
+    /// Name of the setter for the whole field, `None` if skipped.
+    setter: Option<Ident>,
+    /// Whether the setter takes `impl Into<T>`.
+    setter_into: bool,
+    /// Function checking the field value in `build()`.
+    validate: Option<Path>,
+    /// Options of the environment variable read by `from_env()`.
+    env: Option<EnvOpts>,
+    /// Doc comments of the field, copied onto its setters.
+    docs: Vec<&'a Attribute>,
+}
+
+impl BuilderField<'_> {
+    /// `{prefix}{field}()` and `is_{field}_set()` for a field stored as an `Option`.
+    fn getters(&self, prefix: &str) -> proc_macro2::TokenStream {
+        let i = &self.ident;
+        let getter = format_ident!("{}{}", prefix, i.unraw());
+        let is_set = format_ident!("is_{}_set", i.unraw());
+        let getter_type = match &self.kind {
+            FieldKind::Optional(real_type) | FieldKind::SubBuilder(real_type) => real_type,
+            FieldKind::Required | FieldKind::Each { .. } => self.ty,
+        };
+        quote!(
+            pub fn #getter(&self) -> ::core::option::Option<&#getter_type> {
+                ::core::option::Option::as_ref(&self.#i)
+            }
+
+            pub fn #is_set(&self) -> ::core::primitive::bool {
+                ::core::option::Option::is_some(&self.#i)
+            }
+        )
+    }
+
+    /// Parameter type of the setter taking `ty` and the expression turning the parameter into
+    /// the stored value.
+    fn setter_param(&self, ty: &Type) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        let i = &self.ident;
+        if self.setter_into {
+            (
+                quote!(impl ::core::convert::Into<#ty>),
+                quote!(::core::convert::Into::into(#i)),
+            )
+        } else {
+            (quote!(#ty), quote!(#i))
+        }
+    }
+}
+
 #[allow(
     clippy::too_many_lines,
     clippy::missing_errors_doc,
@@ -36,185 +363,1775 @@ macro_rules! compile_span_error {
 pub fn derive(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
     let ident = &ast.ident;
-    let builder_ident = Ident::new(
-        &format!("{}Builder", ident.to_string().as_str()),
-        ident.span(),
-    );
+
+    let opts = match parse_container_opts(&ast.attrs) {
+        Ok(v) => v,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let vis = opts.vis.as_ref().unwrap_or(&ast.vis);
+    // Structs get `Name::builder()` returning `NameBuilder`, enums get one builder per
+    // variant: `Name::variant_builder()` returning `NameVariantBuilder`.
+    let targets: Vec<(Target, &Fields)> = match &ast.data {
+        Data::Struct(data_struct) => vec![(
+            Target {
+                ident,
+                generics: &ast.generics,
+                path: quote!(#ident),
+                constructor: format_ident!("builder"),
+                builder_ident: opts.name.clone().unwrap_or_else(|| {
+                    Ident::new(
+                        &format!("{}Builder", ident.to_string().as_str()),
+                        ident.span(),
+                    )
+                }),
+                vis,
+            },
+            &data_struct.fields,
+        )],
+        Data::Enum(_) if opts.name.is_some() => {
+            return compile_error!(
+                ident.span(),
+                "`name` is only supported on structs, enum builders are named after each variant"
+            );
+        }
+        Data::Enum(_) if opts.to_builder => {
+            return compile_error!(ident.span(), "`to_builder` is only supported on structs");
+        }
+        Data::Enum(data_enum) => data_enum
+            .variants
+            .iter()
+            .map(|variant| {
+                let variant_ident = &variant.ident;
+                (
+                    Target {
+                        ident,
+                        generics: &ast.generics,
+                        path: quote!(#ident::#variant_ident),
+                        constructor: format_ident!(
+                            "{}_builder",
+                            snake_case(&variant_ident.unraw().to_string())
+                        ),
+                        builder_ident: format_ident!("{}{}Builder", ident, variant_ident.unraw()),
+                        vis,
+                    },
+                    &variant.fields,
+                )
+            })
+            .collect(),
+        Data::Union(_) => {
+            return compile_error!(
+                ident.span(),
+                "invalid derive type: unions are not supported"
+            );
+        }
+    };
+
+    let mut expanded = proc_macro2::TokenStream::new();
+    for (target, fields) in &targets {
+        let fields = match builder_fields(fields, &opts) {
+            Ok(v) => v,
+            Err(e) => return e.to_compile_error().into(),
+        };
+        if opts.typestate {
+            if let Some(field) = fields.iter().find(|f| f.validate.is_some()) {
+                return compile_span_error!(
+                    &field.ident,
+                    "`validate` can not be used together with `typestate`, whose `build()` can not fail"
+                );
+            }
+            expanded.extend(typestate_builder(target, &opts, &fields));
+        } else {
+            expanded.extend(runtime_builder(target, &opts, &fields));
+        }
+        if opts.to_builder {
+            expanded.extend(to_builder(target, &fields, opts.typestate));
+        }
+    }
+    expanded.into()
+}
+
+/// Collect the fields of a struct or an enum variant along with their options.
+fn builder_fields<'a>(
+    fields: &'a Fields,
+    opts: &ContainerOpts,
+) -> syn::Result<Vec<BuilderField<'a>>> {
+    let mut builder_fields: Vec<BuilderField> = vec![];
+
+    for (index, field) in fields.iter().enumerate() {
+        let field_opts = parse_field_opts(&field.attrs)?;
+        let each = field_opts.each;
+
+        let (i, member) = if let Some(i) = &field.ident {
+            if let Some(name) = &field_opts.name {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "`name` is only for tuple fields, use `setter(name = \"...\")` instead",
+                ));
+            }
+            (i.clone(), Member::Named(i.clone()))
+        } else {
+            // Tuple fields are stored as `_0`, `_1`, ... unless named.
+            (
+                field_opts
+                    .name
+                    .unwrap_or_else(|| format_ident!("_{}", index)),
+                Member::Unnamed(index.into()),
+            )
+        };
+        let t = &field.ty;
+        let setter = if field_opts.setter_skip {
+            None
+        } else {
+            let bulk = each.as_ref().and_then(|v| v.bulk.clone());
+            if let (Some(bulk), Some(_)) = (&bulk, &field_opts.setter_name) {
+                return Err(syn::Error::new_spanned(
+                    bulk,
+                    "`each(bulk = \"...\")` can not be used together with `setter(name = \"...\")`",
+                ));
+            }
+            let explicit = bulk.or(field_opts.setter_name);
+            match (&each, explicit) {
+                (Some(each), Some(setter)) if setter == each.name => {
+                    return Err(syn::Error::new_spanned(
+                        setter,
+                        "the setter of the whole collection needs another name than the `each` setter",
+                    ));
+                }
+                (_, Some(setter)) => Some(setter),
+                (_, None) => {
+                    // Raw field names like `r#type` stay raw unless a prefix makes them a plain
+                    // name.
+                    let setter = opts.setter_prefix.as_deref().map_or_else(
+                        || i.clone(),
+                        |prefix| format_ident!("{}{}", prefix, i.unraw()),
+                    );
+                    // When the `each` setter is named like the field, the whole collection is
+                    // set through `set_{field}`.
+                    Some(if each.as_ref().is_some_and(|v| v.name == setter) {
+                        format_ident!("set_{}", i.unraw())
+                    } else {
+                        setter
+                    })
+                }
+            }
+        };
+        // Skipped fields can only get their value from the default.
+        let default = if field_opts.setter_skip && field_opts.default.is_none() {
+            Some(quote!(::core::default::Default::default()))
+        } else {
+            field_opts.default
+        };
+        if let Some(merge) = &field_opts.merge {
+            if each.is_none() {
+                return Err(syn::Error::new_spanned(
+                    &merge.0,
+                    "`merge` is only for fields with `each`, other fields are replaced when set",
+                ));
+            }
+            if opts.typestate {
+                return Err(syn::Error::new_spanned(
+                    &merge.0,
+                    "`merge` can not be used together with `typestate`, which has no `merge()`",
+                ));
+            }
+        }
+        if field_opts.env.is_some() && opts.from_env.is_none() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`env` needs `#[builder(from_env)]` on the type",
+            ));
+        }
+        if field_opts.env.as_ref().is_some_and(|v| !v.skip) && field_opts.setter_skip {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`env` can not be used together with `setter(skip)`",
+            ));
+        }
+        let kind = if field_opts.sub_builder {
+            if opts.typestate {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "`sub_builder` can not be used together with `typestate`",
+                ));
+            }
+            if each.is_some() || default.is_some() {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "`sub_builder` can not be used together with `each`, `default` or `setter(skip)`",
+                ));
+            }
+            FieldKind::SubBuilder(sub_builder_type(t).ok_or_else(|| {
+                syn::Error::new_spanned(t, "`sub_builder` needs a named type deriving `Builder`")
+            })?)
+        } else if let Some(inner) = generic_inner(t, OPTION_PATHS) {
+            if each.is_some() {
+                return Err(syn::Error::new_spanned(
+                    t,
+                    "`each` is not supported on `Option` fields, use the collection type instead, it defaults to empty",
+                ));
+            }
+            FieldKind::Optional(inner.clone())
+        } else if let Some(each) = each {
+            if default.is_some() && !field_opts.setter_skip {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "`default` can not be used together with `each`, which defaults to empty",
+                ));
+            }
+            FieldKind::Each {
+                setter: if field_opts.setter_skip {
+                    None
+                } else {
+                    Some(each.name)
+                },
+                item: collection_item(t),
+                into: each.into,
+                merge: field_opts.merge.map(|v| v.1).unwrap_or_default(),
+            }
+        } else {
+            FieldKind::Required
+        };
+        // Setters can not take the name of another method of the builder.
+        let build_name = opts
+            .build_fn
+            .name
+            .clone()
+            .unwrap_or_else(|| format_ident!("build"));
+        let each_setter = match &kind {
+            FieldKind::Each { setter, .. } => setter.as_ref(),
+            _ => None,
+        };
+        let names = setter
+            .iter()
+            .map(|v| (v, "setter(name = \"...\")"))
+            .chain(each_setter.map(|v| (v, "each = \"...\"")));
+        for (name, rename) in names {
+            let reserved = *name == build_name
+                || !opts.typestate && (name == "merge" || name == "missing_fields")
+                || opts.from_env.is_some() && name == "from_env";
+            if reserved {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    format!(
+                        "the setter `{name}` clashes with the method `{name}()` of the builder, rename it with `{rename}`",
+                    ),
+                ));
+            }
+        }
+
+        builder_fields.push(BuilderField {
+            ident: i,
+            member,
+            ty: t,
+            kind,
+            default,
+            setter,
+            setter_into: field_opts.setter_into,
+            validate: field_opts.validate,
+            env: field_opts.env,
+            docs: field
+                .attrs
+                .iter()
+                .filter(|v| v.path().is_ident("doc"))
+                .collect(),
+        });
+    }
+
+    Ok(builder_fields)
+}
+
+fn parse_container_opts(attrs: &[Attribute]) -> syn::Result<ContainerOpts> {
+    let mut opts = ContainerOpts::default();
+    for attr in attrs {
+        if !attr.path().is_ident("builder") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("typestate") {
+                opts.typestate = true;
+            } else if meta.path.is_ident("validate") {
+                opts.validate = Some(parse_path_value(&meta)?);
+            } else if meta.path.is_ident("to_builder") {
+                opts.to_builder = true;
+            } else if meta.path.is_ident("no_std") {
+                opts.no_std = true;
+            } else if meta.path.is_ident("build_fn") {
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("name") {
+                        let v: LitStr = meta.value()?.parse()?;
+                        opts.build_fn.name = Some(parse_ident(&v)?);
+                    } else if meta.path.is_ident("error") {
+                        opts.build_fn.error = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                    } else if meta.path.is_ident("private") {
+                        opts.build_fn.private = true;
+                    } else if meta.path.is_ident("async") {
+                        opts.build_fn.is_async = true;
+                    } else if meta.path.is_ident("with") {
+                        opts.build_fn.with = Some(parse_path_value(&meta)?);
+                    } else {
+                        return Err(meta.error(
+                            "expected `name = \"...\"`, `error = \"...\"`, `private`, `async` or `with = \"...\"`",
+                        ));
+                    }
+                    Ok(())
+                })?;
+            } else if meta.path.is_ident("derive") {
+                meta.parse_nested_meta(|meta| {
+                    let name: Vec<String> = meta
+                        .path
+                        .segments
+                        .iter()
+                        .map(|s| s.ident.to_string())
+                        .collect();
+                    if name != ["Deserialize"] && name != ["serde", "Deserialize"] {
+                        opts.derives.push(meta.path);
+                        return Ok(());
+                    }
+                    if !cfg!(feature = "serde") {
+                        return Err(meta.error(
+                            "`derive(Deserialize)` requires the `serde` feature of derive_builder",
+                        ));
+                    }
+                    opts.deserialize = true;
+                    Ok(())
+                })?;
+            } else if meta.path.is_ident("attr") {
+                let content;
+                parenthesized!(content in meta.input);
+                opts.attrs.push(content.parse()?);
+            } else if meta.path.is_ident("vis") {
+                opts.vis = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("name") {
+                let v: LitStr = meta.value()?.parse()?;
+                opts.name = Some(parse_ident(&v)?);
+            } else if meta.path.is_ident("from_env") {
+                let mut separator = String::from(",");
+                if meta.input.peek(token::Paren) {
+                    meta.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("separator") {
+                            separator = meta.value()?.parse::<LitStr>()?.value();
+                            Ok(())
+                        } else {
+                            Err(meta.error("expected `separator = \"...\"`"))
+                        }
+                    })?;
+                }
+                opts.from_env = Some(separator);
+            } else if meta.path.is_ident("pattern") {
+                let v: LitStr = meta.value()?.parse()?;
+                opts.pattern = Some(match v.value().as_str() {
+                    "owned" => Pattern::Owned,
+                    "mutable" => Pattern::Mutable,
+                    "immutable" => Pattern::Immutable,
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            v,
+                            "expected `owned`, `mutable` or `immutable`",
+                        ))
+                    }
+                });
+            } else if meta.path.is_ident("setter") || meta.path.is_ident("getter") {
+                let prefix = if meta.path.is_ident("setter") {
+                    &mut opts.setter_prefix
+                } else {
+                    &mut opts.getter_prefix
+                };
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("prefix") {
+                        let v: LitStr = meta.value()?.parse()?;
+                        *prefix = Some(v.value());
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `prefix = \"...\"`"))
+                    }
+                })?;
+            } else {
+                return Err(meta.error(
+                    "expected `typestate`, `pattern = \"...\"`, `setter(...)`, `getter(...)`, `validate = \"...\"`, `vis = \"...\"`, `name = \"...\"`, `derive(...)`, `attr(...)`, `to_builder`, `no_std`, `build_fn(...)` or `from_env` in `builder(...)`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    if opts.typestate && opts.deserialize {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`derive(Deserialize)` can not be used together with `typestate`",
+        ));
+    }
+    if opts.no_std && opts.from_env.is_some() {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`from_env` can not be used together with `no_std`",
+        ));
+    }
+    if opts.typestate && opts.from_env.is_some() {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`from_env` can not be used together with `typestate`",
+        ));
+    }
+    if opts.typestate
+        && (opts.build_fn.error.is_some() || opts.build_fn.is_async || opts.build_fn.with.is_some())
+    {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "the typestate builder only supports `build_fn(name = \"...\", private)`",
+        ));
+    }
+    if opts.typestate && opts.validate.is_some() {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`validate` can not be used together with `typestate`, whose `build()` can not fail",
+        ));
+    }
+    if opts.typestate && opts.pattern.is_some_and(|v| v != Pattern::Owned) {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "the typestate builder always uses `pattern = \"owned\"`",
+        ));
+    }
+    Ok(opts)
+}
+
+fn parse_field_opts(attrs: &[Attribute]) -> syn::Result<FieldOpts> {
+    let mut opts = FieldOpts::default();
+    for attr in attrs {
+        if !attr.path().is_ident("builder") {
+            continue;
+        }
+        // Here we can use format! to show custom message but in
+        // test 08 it requires a const one.
+        // format!("unknown attribute {}", i)
+        //
+        // Test 08 requires the error message to mark all tokens
+        // inside the unrecognized attribute, thus use new_spanned()
+        // instead of new(), the latter one only mark the current
+        // span.
+        //
+        let unknown = || syn::Error::new_spanned(&attr.meta, "expected `builder(each = \"...\")`");
+        if !matches!(attr.meta, Meta::List(_)) {
+            return Err(unknown());
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("each") {
+                if meta.input.peek(token::Paren) {
+                    // Long form: `each(name = "...", bulk = "...", into)`.
+                    let mut name: Option<Ident> = None;
+                    let mut bulk: Option<Ident> = None;
+                    let mut into = false;
+                    meta.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("name") {
+                            let v: LitStr = meta.value()?.parse()?;
+                            name = Some(parse_ident(&v)?);
+                        } else if meta.path.is_ident("bulk") {
+                            let v: LitStr = meta.value()?.parse()?;
+                            bulk = Some(parse_ident(&v)?);
+                        } else if meta.path.is_ident("into") {
+                            into = true;
+                        } else {
+                            return Err(
+                                meta.error("expected `name = \"...\"`, `bulk = \"...\"` or `into`")
+                            );
+                        }
+                        Ok(())
+                    })?;
+                    let Some(name) = name else {
+                        return Err(meta.error("missing `name = \"...\"` in `each(...)`"));
+                    };
+                    opts.each = Some(EachOpts { name, bulk, into });
+                } else {
+                    let v: LitStr = meta.value()?.parse()?;
+                    opts.each = Some(EachOpts {
+                        name: parse_ident(&v)?,
+                        bulk: None,
+                        into: false,
+                    });
+                }
+            } else if meta.path.is_ident("setter") {
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("into") {
+                        opts.setter_into = true;
+                    } else if meta.path.is_ident("skip") {
+                        opts.setter_skip = true;
+                    } else if meta.path.is_ident("name") {
+                        let v: LitStr = meta.value()?.parse()?;
+                        opts.setter_name = Some(parse_ident(&v)?);
+                    } else {
+                        return Err(meta.error("expected `into`, `skip` or `name = \"...\"`"));
+                    }
+                    Ok(())
+                })?;
+            } else if meta.path.is_ident("validate") {
+                opts.validate = Some(parse_path_value(&meta)?);
+            } else if meta.path.is_ident("name") {
+                let v: LitStr = meta.value()?.parse()?;
+                opts.name = Some(parse_ident(&v)?);
+            } else if meta.path.is_ident("env") {
+                let mut env = EnvOpts {
+                    name: None,
+                    separator: None,
+                    skip: false,
+                };
+                if meta.input.peek(token::Paren) {
+                    // Long form: `env(name = "...", separator = "...")` or `env(skip)`.
+                    meta.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("name") {
+                            env.name = Some(meta.value()?.parse()?);
+                        } else if meta.path.is_ident("separator") {
+                            env.separator = Some(meta.value()?.parse::<LitStr>()?.value());
+                        } else if meta.path.is_ident("skip") {
+                            env.skip = true;
+                        } else {
+                            return Err(meta.error(
+                                "expected `name = \"...\"`, `separator = \"...\"` or `skip`",
+                            ));
+                        }
+                        Ok(())
+                    })?;
+                    if env.skip && (env.name.is_some() || env.separator.is_some()) {
+                        return Err(meta.error(
+                            "`env(skip)` can not be used together with `name` or `separator`",
+                        ));
+                    }
+                } else {
+                    env.name = Some(meta.value()?.parse()?);
+                }
+                opts.env = Some(env);
+            } else if meta.path.is_ident("sub_builder") {
+                opts.sub_builder = true;
+            } else if meta.path.is_ident("merge") {
+                let v: LitStr = meta.value()?.parse()?;
+                let mode = match v.value().as_str() {
+                    "append" => MergeMode::Append,
+                    "replace" => MergeMode::Replace,
+                    _ => return Err(syn::Error::new_spanned(v, "expected `append` or `replace`")),
+                };
+                opts.merge = Some((v, mode));
+            } else if meta.path.is_ident("default") {
+                // `default`, `default = "expr"` or `default = expr`.
+                opts.default = Some(if meta.input.peek(Token![=]) {
+                    let value = meta.value()?;
+                    if value.peek(LitStr) {
+                        value.parse::<LitStr>()?.parse::<Expr>()?.to_token_stream()
+                    } else {
+                        value.parse::<Expr>()?.to_token_stream()
+                    }
+                } else {
+                    quote!(::core::default::Default::default())
+                });
+            } else {
+                return Err(unknown());
+            }
+            Ok(())
+        })?;
+    }
+    Ok(opts)
+}
+
+/// Parse the name given in `v`, e.g. the `"..."` of `setter(name = "...")`.
+fn parse_ident(v: &LitStr) -> syn::Result<Ident> {
+    v.parse().map_err(|_| {
+        syn::Error::new_spanned(v, format!("`{}` is not a valid identifier", v.value()))
+    })
+}
+
+/// Parse `key = "path::to::fn"` or `key = path::to::fn`.
+fn parse_path_value(meta: &ParseNestedMeta) -> syn::Result<Path> {
+    let value = meta.value()?;
+    if value.peek(LitStr) {
+        value.parse::<LitStr>()?.parse()
+    } else {
+        value.parse()
+    }
+}
+
+/// Generate the default builder, which stores every field as an `Option` and reports unset
+/// required fields from `build()`.
+fn runtime_builder(
+    target: &Target,
+    opts: &ContainerOpts,
+    fields: &[BuilderField],
+) -> proc_macro2::TokenStream {
+    let Target {
+        ident,
+        generics,
+        path,
+        constructor,
+        builder_ident,
+        vis,
+    } = target;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let pattern = opts.pattern.unwrap_or_default();
+    let (receiver, ret, bind) = pattern.setter();
+    let setter_fn = |docs: &[&Attribute],
+                     name: &Ident,
+                     param: &Ident,
+                     param_type: &proc_macro2::TokenStream,
+                     update: proc_macro2::TokenStream| {
+        quote!(
+            #(#docs)*
+            pub fn #name(#receiver, #param: #param_type) -> #ret {
+                #bind
+                #update
+                builder
+            }
+        )
+    };
+    let build_receiver = pattern.build_receiver();
+    // Everything else comes from `core`, which is available with and without `std`.
+    let (alloc, error_trait) = if opts.no_std {
+        (quote!(::alloc), quote!(::core::error::Error))
+    } else {
+        (quote!(::std), quote!(::std::error::Error))
+    };
+    let error_ident = format_ident!("{}Error", builder_ident);
+    let error_doc = format!("Error returned when building a [`{ident}`] fails.");
 
     let mut field_vec: Vec<proc_macro2::TokenStream> = vec![];
     let mut empty_field_vec: Vec<proc_macro2::TokenStream> = vec![];
     let mut field_method_vec: Vec<proc_macro2::TokenStream> = vec![];
     let mut field_build_vec: Vec<proc_macro2::TokenStream> = vec![];
+    let mut construct_vec: Vec<proc_macro2::TokenStream> = vec![];
+    // Locals holding the field values in `build()`, passed to `build_fn(with = "...")`.
+    let mut local_vec: Vec<Ident> = vec![];
+    // Checks for required fields, each one records the field name if it is still unset.
+    let mut field_check_vec: Vec<proc_macro2::TokenStream> = vec![];
+    // Calls to `validate` functions on the built value.
+    let mut validate_vec: Vec<proc_macro2::TokenStream> = vec![];
+    // Statements taking the fields set in `other` in `merge()`.
+    let mut merge_vec: Vec<proc_macro2::TokenStream> = vec![];
+    // Statements reading fields from the environment in `from_env()`.
+    let mut env_vec: Vec<proc_macro2::TokenStream> = vec![];
+    // Getters and `is_{field}_set()` for every field.
+    let mut getter_vec: Vec<proc_macro2::TokenStream> = vec![];
+    let getter_prefix = opts.getter_prefix.as_deref().unwrap_or("get_");
 
-    let Data::Struct(data_struct) = &ast.data else {
-        return compile_error!(ident.span(), "invalid derive type: not a struct");
+    for field in fields {
+        let i = &field.ident;
+        let m = &field.member;
+        let t = field.ty;
+        let stored = pattern.take(i);
+        // `build()` binds every field to a local before constructing the value.
+        let local = format_ident!("__{}", i.unraw());
+        construct_vec.push(quote!(#m: #local));
+        local_vec.push(local.clone());
+        match &field.kind {
+            FieldKind::Optional(real_type) => {
+                field_vec.push(quote!(
+                    #i: #t
+                ));
+                field_build_vec.push(field.default.as_ref().map_or_else(
+                    || quote!(let #local = #stored;),
+                    |default| quote!(let #local = ::core::option::Option::or_else(#stored, || #default);),
+                ));
+                if let Some(setter) = &field.setter {
+                    let (param_type, value) = field.setter_param(real_type);
+                    field_method_vec.push(setter_fn(
+                        &field.docs,
+                        setter,
+                        i,
+                        &param_type,
+                        quote!(builder.#i = ::core::option::Option::Some(#value);),
+                    ));
+                }
+            }
+            FieldKind::Each {
+                setter: each_ident,
+                item,
+                into,
+                merge,
+            } => {
+                merge_vec.push(match merge {
+                    MergeMode::Append => quote!(
+                        if let ::core::option::Option::Some(items) = other.#i {
+                            ::core::iter::Extend::extend(
+                                self.#i.get_or_insert_with(::core::default::Default::default),
+                                items,
+                            );
+                        }
+                    ),
+                    MergeMode::Replace => quote!(
+                        if other.#i.is_some() {
+                            self.#i = other.#i;
+                        }
+                    ),
+                });
+                field_vec.push(quote!(
+                    #i: ::core::option::Option<#t>
+                ));
+                // Fields with `each` are allowed to stay empty.
+                field_build_vec.push(quote!(
+                    let #local = ::core::option::Option::unwrap_or_default(#stored);
+                ));
+                if let Some(each_ident) = each_ident {
+                    let (param_type, value) = each_param(each_ident, item, *into);
+                    field_method_vec.push(setter_fn(
+                        &field.docs,
+                        each_ident,
+                        each_ident,
+                        &param_type,
+                        quote!(
+                            ::core::iter::Extend::extend(
+                                builder.#i.get_or_insert_with(::core::default::Default::default),
+                                ::core::iter::once(#value),
+                            );
+                        ),
+                    ));
+                    if let Some(setter) = &field.setter {
+                        let (param_type, value) = field.setter_param(t);
+                        field_method_vec.push(setter_fn(
+                            &field.docs,
+                            setter,
+                            i,
+                            &param_type,
+                            quote!(builder.#i = ::core::option::Option::Some(#value);),
+                        ));
+                    }
+                    let (extend, clear) = each_extend_clear(i);
+                    let item = item.to_type_tokens();
+                    let field_docs = &field.docs;
+                    field_method_vec.push(setter_fn(
+                        &field.docs,
+                        &extend,
+                        i,
+                        &quote!(impl ::core::iter::IntoIterator<Item = #item>),
+                        quote!(
+                            ::core::iter::Extend::extend(
+                                builder.#i.get_or_insert_with(::core::default::Default::default),
+                                #i,
+                            );
+                        ),
+                    ));
+                    field_method_vec.push(quote!(
+                        #(#field_docs)*
+                        pub fn #clear(#receiver) -> #ret {
+                            #bind
+                            builder.#i = ::core::option::Option::Some(::core::default::Default::default());
+                            builder
+                        }
+                    ));
+                }
+            }
+            FieldKind::SubBuilder(sub) => {
+                field_vec.push(quote!(
+                    #i: ::core::option::Option<#sub>
+                ));
+                // An unset sub-builder is built empty, so it reports its own missing fields. A
+                // stored one is taken by an owned outer builder and borrowed by the others.
+                let sub_built = match pattern {
+                    Pattern::Owned => quote_spanned!(t.span()=>
+                        let #local = <#sub>::__build_sub_builder(
+                            ::core::option::Option::unwrap_or_else(builder.#i, <#t>::builder),
+                        );
+                    ),
+                    Pattern::Mutable | Pattern::Immutable => quote_spanned!(t.span()=>
+                        let #local = match &builder.#i {
+                            ::core::option::Option::Some(b) => <#sub>::__build_sub_builder_ref(b),
+                            ::core::option::Option::None => {
+                                <#sub>::__build_sub_builder(<#t>::builder())
+                            }
+                        };
+                    ),
+                };
+                let name = ident_token_str!(i);
+                field_build_vec.push(quote!(
+                    #sub_built
+                    let #local = match #local {
+                        ::core::result::Result::Ok(v) => v,
+                        ::core::result::Result::Err(e) => {
+                            let path = match e.field_path() {
+                                ::core::option::Option::Some(path) => {
+                                    #alloc::format!("{}.{}", #name, path)
+                                }
+                                ::core::option::Option::None => {
+                                    #alloc::string::ToString::to_string(#name)
+                                }
+                            };
+                            return ::core::result::Result::Err(::core::convert::From::from(
+                                #error_ident::Nested {
+                                    path,
+                                    message: e.__nested_message(),
+                                },
+                            ));
+                        }
+                    };
+                ));
+                merge_vec.push(quote!(
+                    if let ::core::option::Option::Some(other) = other.#i {
+                        match &mut self.#i {
+                            ::core::option::Option::Some(builder) => {
+                                builder.merge(other);
+                            }
+                            ::core::option::Option::None => {
+                                self.#i = ::core::option::Option::Some(other);
+                            }
+                        }
+                    }
+                ));
+                let sub_mut = format_ident!("{}_mut", i.unraw());
+                field_method_vec.push(quote!(
+                    /// Builder of the field, created empty on first use.
+                    pub fn #sub_mut(&mut self) -> &mut #sub {
+                        self.#i.get_or_insert_with(<#t>::builder)
+                    }
+                ));
+                if let Some(setter) = &field.setter {
+                    let (param_type, value) = field.setter_param(sub);
+                    field_method_vec.push(setter_fn(
+                        &field.docs,
+                        setter,
+                        i,
+                        &param_type,
+                        quote!(builder.#i = ::core::option::Option::Some(#value);),
+                    ));
+                }
+            }
+            FieldKind::Required => {
+                field_vec.push(quote!(
+                    #i: ::core::option::Option<#t>
+                ));
+                if let Some(default) = &field.default {
+                    field_build_vec.push(quote!(
+                        let #local = ::core::option::Option::unwrap_or_else(#stored, || #default);
+                    ));
+                } else {
+                    let name = ident_token_str!(i);
+                    field_check_vec.push(quote!(
+                        if self.#i.is_none() {
+                            missing.push(#name);
+                        }
+                    ));
+                    field_build_vec.push(quote!(
+                        let #local = ::core::option::Option::unwrap(#stored);
+                    ));
+                }
+                if let Some(setter) = &field.setter {
+                    let (param_type, value) = field.setter_param(t);
+                    field_method_vec.push(setter_fn(
+                        &field.docs,
+                        setter,
+                        i,
+                        &param_type,
+                        quote!(builder.#i = ::core::option::Option::Some(#value);),
+                    ));
+                }
+            }
+        }
+        getter_vec.push(field.getters(getter_prefix));
+        if let Some(separator) = &opts.from_env {
+            env_vec.extend(env_statement(field, separator, &error_ident));
+        }
+        if !matches!(
+            field.kind,
+            FieldKind::Each { .. } | FieldKind::SubBuilder(_)
+        ) {
+            merge_vec.push(quote!(
+                if other.#i.is_some() {
+                    self.#i = other.#i;
+                }
+            ));
+        }
+        empty_field_vec.push(quote!(
+            #i: ::core::option::Option::None
+        ));
+        if let Some(validate) = &field.validate {
+            let name = ident_token_str!(i);
+            validate_vec.push(quote!(
+                if let ::core::result::Result::Err(e) = #validate(&#local) {
+                    return ::core::result::Result::Err(::core::convert::From::from(
+                        #error_ident::InvalidField {
+                            field: #name,
+                            message: #alloc::string::ToString::to_string(&e),
+                        },
+                    ));
+                }
+            ));
+        }
+    }
+    let struct_validate = opts.validate.as_ref().map(|validate| {
+        quote!(
+            if let ::core::result::Result::Err(e) = #validate(&value) {
+                return ::core::result::Result::Err(::core::convert::From::from(
+                    #error_ident::Invalid(#alloc::string::ToString::to_string(&e)),
+                ));
+            }
+        )
+    });
+    let BuildFnOpts {
+        name: build_name,
+        error: build_error,
+        private: build_private,
+        is_async,
+        with: build_with,
+    } = &opts.build_fn;
+    let build_name = build_name.clone().unwrap_or_else(|| format_ident!("build"));
+    let build_vis = (!build_private).then(|| quote!(pub));
+    let build_async = is_async.then(|| quote!(async));
+    let build_error = build_error
+        .as_ref()
+        .map_or_else(|| quote!(#error_ident), ToTokens::to_token_stream);
+    let construct = |is_async: bool| {
+        if let Some(with) = build_with {
+            let call = quote!(#with(#(#local_vec),*));
+            if is_async {
+                quote!(#call.await?)
+            } else {
+                quote!(#call?)
+            }
+        } else {
+            quote!(#path {
+                #(#construct_vec,)*
+            })
+        }
+    };
+    // Statements of `build()` after binding the builder to read as `builder`.
+    let build_body = |construct: proc_macro2::TokenStream| {
+        quote!(
+            let missing = builder.missing_fields();
+            if !missing.is_empty() {
+                return ::core::result::Result::Err(::core::convert::From::from(
+                    #error_ident::MissingFields(missing),
+                ));
+            }
+            #(#field_build_vec)*
+            #(#validate_vec)*
+            let value = #construct;
+            #struct_validate
+            ::core::result::Result::Ok(value)
+        )
     };
+    let build_stmts = build_body(construct(*is_async));
+    // An outer builder builds `sub_builder` fields synchronously with the generated error, by
+    // value or from a reference whatever the pattern. Not possible when the value comes from
+    // an async or custom error `build_fn(with)`.
+    let sub_build_fns = (build_with.is_none() || (!is_async && opts.build_fn.error.is_none())).then(|| {
+        let sub_vis = if *build_private {
+            quote!(pub(crate))
+        } else {
+            quote!(pub)
+        };
+        let sub_stmts = build_body(construct(false));
+        // An owned builder is cloned to be built from a reference.
+        let (ref_bound, ref_bind) = match pattern {
+            Pattern::Owned => (
+                quote!(where for<'__a> Self: ::core::clone::Clone),
+                quote!(let builder = ::core::clone::Clone::clone(self);),
+            ),
+            Pattern::Mutable | Pattern::Immutable => (quote!(), quote!(let builder = self;)),
+        };
+        quote!(
+            #[doc(hidden)]
+            #sub_vis fn __build_sub_builder(self) -> ::core::result::Result<#ident #ty_generics, #error_ident> {
+                let builder = self;
+                #sub_stmts
+            }
 
-    let Fields::Named(named_fields) = &data_struct.fields else {
-        return compile_error!(ident.span(), "expected named fields");
+            #[doc(hidden)]
+            #sub_vis fn __build_sub_builder_ref(&self) -> ::core::result::Result<#ident #ty_generics, #error_ident>
+            #ref_bound
+            {
+                #ref_bind
+                #sub_stmts
+            }
+        )
+    });
+
+    // Immutable setters clone the whole builder.
+    let builder_attrs = opts.builder_attrs(pattern == Pattern::Immutable);
+    // A deserialized builder is a partial layer, fields missing from the input stay unset.
+    let (field_attr, marker_attr) = if opts.deserialize {
+        (quote!(#[serde(default)]), quote!(#[serde(skip)]))
+    } else {
+        (quote!(), quote!())
     };
+    let field_vec = field_vec.iter().map(|f| quote!(#field_attr #f));
 
-    for named_field in &named_fields.named {
-        let mut each: Option<String> = None;
-        for attr in &named_field.attrs {
-            let p = attr.meta.path().segments.first();
-            if p.is_none() || p.unwrap().ident != "builder" {
-                continue;
+    let from_env_fn = opts.from_env.is_some().then(|| {
+        quote!(
+            /// Create a builder from environment variables.
+            ///
+            /// Every field is read from `PREFIX_FIELD` (or just `FIELD` when `prefix` is
+            /// empty) unless it has `#[builder(env = "...")]`, and parsed with `FromStr`.
+            /// Unset variables leave their field unset, `each` collections are split on the
+            /// separator. Sub-builders are created with their own `from_env()`, using
+            /// `PREFIX_FIELD` as prefix.
+            #[allow(unused)]
+            pub fn from_env(prefix: &::core::primitive::str) -> ::core::result::Result<Self, #error_ident> {
+                fn parse<T>(variable: &::core::primitive::str, value: &::core::primitive::str) -> ::core::result::Result<T, #error_ident>
+                where
+                    T: ::core::str::FromStr,
+                    <T as ::core::str::FromStr>::Err: ::core::fmt::Display,
+                {
+                    ::core::result::Result::map_err(<T as ::core::str::FromStr>::from_str(value), |e| {
+                        #error_ident::InvalidEnv {
+                            variable: ::std::string::String::from(variable),
+                            message: ::std::string::ToString::to_string(&e),
+                        }
+                    })
+                }
+                fn read(
+                    variable: &::core::primitive::str,
+                ) -> ::core::result::Result<::core::option::Option<::std::string::String>, #error_ident> {
+                    match ::std::env::var(variable) {
+                        ::core::result::Result::Ok(value) => {
+                            ::core::result::Result::Ok(::core::option::Option::Some(value))
+                        }
+                        ::core::result::Result::Err(::std::env::VarError::NotPresent) => {
+                            ::core::result::Result::Ok(::core::option::Option::None)
+                        }
+                        ::core::result::Result::Err(e) => {
+                            ::core::result::Result::Err(#error_ident::InvalidEnv {
+                                variable: ::std::string::String::from(variable),
+                                message: ::std::string::ToString::to_string(&e),
+                            })
+                        }
+                    }
+                }
+                let mut builder = Self {
+                    #(#empty_field_vec,)*
+                    __marker: ::core::marker::PhantomData,
+                };
+                #(#env_vec)*
+                ::core::result::Result::Ok(builder)
             }
-            let Meta::List(meta) = &attr.meta else {
-                continue;
-            };
+        )
+    });
 
-            for x in meta.tokens.clone() {
-                match x {
-                    proc_macro2::TokenTree::Ident(i) => {
-                        if i == "each" {
-                            continue;
-                        }
-                        // Here we can use format! to show custom message but in
-                        // test 08 it requires a const one.
-                        // format!("unknown attribute {}", i)
-                        //
-                        // Test 08 requires the error message to mark all tokens
-                        // inside the unrecognized attribute, thus use new_spanned()
-                        // instead of new(), the latter one only mark the current
-                        // span.
-                        //
-                        return compile_span_error!(
-                            &attr.meta,
-                            "expected `builder(each = \"...\")`".to_string()
-                        );
+    quote!(
+        impl #impl_generics #ident #ty_generics #where_clause {
+            pub fn #constructor() -> #builder_ident #ty_generics {
+                #builder_ident {
+                    #(#empty_field_vec,)*
+                    __marker: ::core::marker::PhantomData,
+                }
+            }
+        }
+
+        #builder_attrs
+        #vis struct #builder_ident #generics #where_clause {
+            #(#field_vec,)*
+            // Enum variants may not use every parameter of the enum.
+            #marker_attr
+            __marker: ::core::marker::PhantomData<fn() -> #ident #ty_generics>,
+        }
+
+        #[doc = #error_doc]
+        #[derive(
+            ::core::fmt::Debug,
+            ::core::clone::Clone,
+            ::core::cmp::PartialEq,
+            ::core::cmp::Eq,
+        )]
+        #vis enum #error_ident {
+            /// Required fields that were never set, in declaration order.
+            MissingFields(#alloc::vec::Vec<&'static ::core::primitive::str>),
+            /// A sub-builder failed, `path` is the field that failed, e.g. `inner.port`.
+            Nested {
+                path: #alloc::string::String,
+                message: #alloc::string::String,
+            },
+            /// A field value was rejected by its `validate` function.
+            InvalidField {
+                field: &'static ::core::primitive::str,
+                message: #alloc::string::String,
+            },
+            /// The built value was rejected by the struct `validate` function.
+            Invalid(#alloc::string::String),
+            /// An environment variable read by `from_env()` could not be parsed.
+            InvalidEnv {
+                variable: #alloc::string::String,
+                message: #alloc::string::String,
+            },
+        }
+
+        impl ::core::fmt::Display for #error_ident {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match self {
+                    Self::MissingFields(fields) => {
+                        f.write_str("missing required field(s): ")?;
+                        for (index, field) in ::core::iter::Iterator::enumerate(fields.iter()) {
+                            if index > 0 {
+                                f.write_str(", ")?;
+                            }
+                            ::core::write!(f, "`{}`", field)?;
+                        }
+                        ::core::result::Result::Ok(())
                     }
-                    proc_macro2::TokenTree::Literal(l) => {
-                        each = Some(String::from(l.to_string().trim_matches('"')));
-                        break;
+                    Self::InvalidField { field, message } => {
+                        ::core::write!(f, "invalid value for `{}`: {}", field, message)
+                    }
+                    Self::Invalid(message) => ::core::write!(f, "invalid value: {}", message),
+                    Self::Nested { path, message } => ::core::write!(f, "`{}`: {}", path, message),
+                    Self::InvalidEnv { variable, message } => {
+                        ::core::write!(f, "invalid value in `{}`: {}", variable, message)
                     }
-                    _ => continue,
                 }
             }
-            break;
         }
 
-        let i = &named_field.ident;
-        let t = &named_field.ty;
-        let Type::Path(path) = t else { continue };
+        impl #error_trait for #error_ident {}
 
-        let segments = &path.path.segments;
-        if !segments.is_empty() && segments[0].ident.to_string().as_str() == "Option" {
-            field_vec.push(quote!(
-                #i: #t
-            ));
-            field_build_vec.push(quote!(
-                #i: self.#i.clone()
-            ));
+        impl #error_ident {
+            /// Path of the field that failed, `None` if the failure is not about one field.
+            ///
+            /// The first field is used when several are missing.
+            pub fn field_path(&self) -> ::core::option::Option<#alloc::string::String> {
+                match self {
+                    Self::MissingFields(fields) => ::core::option::Option::map(
+                        ::core::iter::Iterator::next(&mut fields.iter()),
+                        |field| #alloc::string::ToString::to_string(field),
+                    ),
+                    Self::Nested { path, .. } => {
+                        ::core::option::Option::Some(::core::clone::Clone::clone(path))
+                    }
+                    Self::InvalidField { field, .. } => {
+                        ::core::option::Option::Some(#alloc::string::ToString::to_string(field))
+                    }
+                    Self::Invalid(_) | Self::InvalidEnv { .. } => ::core::option::Option::None,
+                }
+            }
 
-            match segments[0]
-                .arguments
-                .to_token_stream()
-                .to_string()
-                .split(' ')
-                .find(|e| !OPTION_MODIFIER.contains(e))
-            {
-                Some(v) => {
-                    let real_type = Ident::new(v, ident.span());
+            /// Message of a nested error, without the path of nested sub-builders.
+            #[doc(hidden)]
+            pub fn __nested_message(&self) -> #alloc::string::String {
+                match self {
+                    Self::Nested { message, .. } => ::core::clone::Clone::clone(message),
+                    _ => #alloc::string::ToString::to_string(self),
+                }
+            }
+        }
+
+        impl #impl_generics #builder_ident #ty_generics #where_clause {
+            #build_vis #build_async fn #build_name(#build_receiver) -> ::core::result::Result<#ident #ty_generics, #build_error> {
+                let builder = self;
+                #build_stmts
+            }
+
+            /// Required fields without default that are still unset, in declaration order.
+            pub fn missing_fields(&self) -> #alloc::vec::Vec<&'static ::core::primitive::str> {
+                let mut missing: #alloc::vec::Vec<&'static ::core::primitive::str> = #alloc::vec::Vec::new();
+                #(#field_check_vec)*
+                missing
+            }
+
+            #(#getter_vec)*
+
+            /// Take every field set in `other`, keeping the current value of fields it left
+            /// unset.
+            ///
+            /// `each` collections are extended with the items of `other` unless the field has
+            /// `#[builder(merge = "replace")]`.
+            pub fn merge(&mut self, other: Self) -> &mut Self {
+                #(#merge_vec)*
+                self
+            }
+
+            #from_env_fn
+            #sub_build_fns
+            #(#field_method_vec)*
+        }
+    )
+}
+
+/// Generate the `#[builder(typestate)]` builder.
+///
+/// Every required field gets its own type parameter on the builder, which is `()` while the
+/// field is unset and `(T,)` once the setter has been called. Each setter is only implemented
+/// for the unset state and `build()` only for the state where every required field is set, so
+/// forgetting a field (or setting it twice) is a compile error and the builder carries no
+/// runtime flags for required fields.
+///
+/// ```ignore
+/// pub struct CommandBuilder<__S0, __S1> {
+///     executable: __S0,
+///     args: __S1,
+///     current_dir: Option<String>,
+/// }
+///
+/// impl<__S1> CommandBuilder<(), __S1> {
+///     pub fn executable(self, executable: String) -> CommandBuilder<(String,), __S1> { ... }
+/// }
+///
+/// impl CommandBuilder<(String,), (Vec<String>,)> {
+///     pub fn build(self) -> Command { ... }
+/// }
+/// ```
+fn typestate_builder(
+    target: &Target,
+    opts: &ContainerOpts,
+    fields: &[BuilderField],
+) -> proc_macro2::TokenStream {
+    let Target {
+        ident,
+        generics,
+        path,
+        constructor,
+        builder_ident,
+        vis,
+    } = target;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let struct_args = generic_args(generics);
+    let state_params: Vec<Ident> = fields
+        .iter()
+        .filter(|f| matches!(f.kind, FieldKind::Required) && f.default.is_none())
+        .enumerate()
+        .map(|(index, _)| format_ident!("__S{}", index))
+        .collect();
+
+    let mut field_vec: Vec<proc_macro2::TokenStream> = vec![];
+    let mut empty_field_vec: Vec<proc_macro2::TokenStream> = vec![];
+    let mut field_build_vec: Vec<proc_macro2::TokenStream> = vec![];
+    // Setters for optional and `each` fields, available in every state.
+    let mut field_method_vec: Vec<proc_macro2::TokenStream> = vec![];
+    // Setters for required fields, each one lives in its own impl block.
+    let mut state_method_vec: Vec<proc_macro2::TokenStream> = vec![];
+    let mut unset_states: Vec<proc_macro2::TokenStream> = vec![];
+    let mut set_states: Vec<proc_macro2::TokenStream> = vec![];
+
+    let getter_prefix = opts.getter_prefix.as_deref().unwrap_or("get_");
+    let mut state_index = 0;
+    for field in fields {
+        let i = &field.ident;
+        let m = &field.member;
+        let t = field.ty;
+        let docs = &field.docs;
+        match &field.kind {
+            FieldKind::Optional(real_type) => {
+                field_method_vec.push(field.getters(getter_prefix));
+                field_vec.push(quote!(#i: #t));
+                empty_field_vec.push(quote!(#i: ::core::option::Option::None));
+                field_build_vec.push(field.default.as_ref().map_or_else(
+                    || quote!(#m: self.#i),
+                    |default| quote!(#m: ::core::option::Option::or_else(self.#i, || #default)),
+                ));
+                if let Some(setter) = &field.setter {
+                    let (param_type, value) = field.setter_param(real_type);
                     field_method_vec.push(quote!(
-                        pub fn #i(&mut self, #i: #real_type) -> &mut Self {
-                            self.#i = Some(#i);
+                        #(#docs)*
+                        pub fn #setter(mut self, #i: #param_type) -> Self {
+                            self.#i = ::core::option::Option::Some(#value);
                             self
                         }
                     ));
                 }
-                None => {
-                    return compile_span_error!(
-                        &segments[0],
-                        "can not find type in Option".to_string()
-                    );
-                }
             }
-        } else {
-            field_vec.push(quote!(
-                #i: std::option::Option<#t>
-            ));
-            field_build_vec.push(quote!(
-                #i: self.#i.clone().unwrap_or_default()
-            ));
-
-            let method = if let Some(v) = each {
-                if segments.is_empty() || segments[0].ident != "Vec" {
-                    return compile_span_error!(
-                        &segments[0],
-                        "`each=()` used on a not vector field".to_string()
-                    );
+            // Fields with a default do not need to be tracked in the builder type.
+            FieldKind::Required if field.default.is_some() => {
+                field_method_vec.push(field.getters(getter_prefix));
+                let default = field.default.as_ref().unwrap();
+                field_vec.push(quote!(#i: ::core::option::Option<#t>));
+                empty_field_vec.push(quote!(#i: ::core::option::Option::None));
+                field_build_vec.push(quote!(
+                    #m: ::core::option::Option::unwrap_or_else(self.#i, || #default)
+                ));
+                if let Some(setter) = &field.setter {
+                    let (param_type, value) = field.setter_param(t);
+                    field_method_vec.push(quote!(
+                        #(#docs)*
+                        pub fn #setter(mut self, #i: #param_type) -> Self {
+                            self.#i = ::core::option::Option::Some(#value);
+                            self
+                        }
+                    ));
                 }
-                match segments[0]
-                    .arguments
-                    .to_token_stream()
-                    .to_string()
-                    .split(' ')
-                    .find(|e| !OPTION_MODIFIER.contains(e))
-                {
-                    Some(vv) => {
-                        let each_ident = Ident::new(v.as_str(), ident.span());
-                        let vec_type_ident = Ident::new(vv, ident.span());
-                        quote!(
-                            pub fn #each_ident(&mut self, #each_ident: #vec_type_ident) -> &mut Self {
-                                if self.#i.is_none() {
-                                    self.#i = Some(Vec::new())
-                                }
-                                if let Some(ref mut v) =  self.#i {
-                                    v.push(#each_ident);
-                                }
+            }
+            FieldKind::Each {
+                setter, item, into, ..
+            } => {
+                field_method_vec.push(field.getters(getter_prefix));
+                field_vec.push(quote!(#i: ::core::option::Option<#t>));
+                empty_field_vec.push(quote!(#i: ::core::option::Option::None));
+                field_build_vec
+                    .push(quote!(#m: ::core::option::Option::unwrap_or_default(self.#i)));
+                if let Some(setter) = setter {
+                    let (param_type, value) = each_param(setter, item, *into);
+                    field_method_vec.push(quote!(
+                        #(#docs)*
+                        pub fn #setter(mut self, #setter: #param_type) -> Self {
+                            ::core::iter::Extend::extend(
+                                self.#i.get_or_insert_with(::core::default::Default::default),
+                                ::core::iter::once(#value),
+                            );
+                            self
+                        }
+                    ));
+                    if let Some(bulk) = &field.setter {
+                        let (param_type, value) = field.setter_param(t);
+                        field_method_vec.push(quote!(
+                            #(#docs)*
+                            pub fn #bulk(mut self, #i: #param_type) -> Self {
+                                self.#i = ::core::option::Option::Some(#value);
                                 self
                             }
-                        )
-                    }
-                    None => {
-                        return compile_span_error!(
-                            &segments[0],
-                            "can not find what T when parsing as Vec<T>"
-                        );
+                        ));
                     }
+                    let (extend, clear) = each_extend_clear(i);
+                    let item = item.to_type_tokens();
+                    field_method_vec.push(quote!(
+                        #(#docs)*
+                        pub fn #extend(mut self, #i: impl ::core::iter::IntoIterator<Item = #item>) -> Self {
+                            ::core::iter::Extend::extend(
+                                self.#i.get_or_insert_with(::core::default::Default::default),
+                                #i,
+                            );
+                            self
+                        }
+
+                        #(#docs)*
+                        pub fn #clear(mut self) -> Self {
+                            self.#i = ::core::option::Option::Some(::core::default::Default::default());
+                            self
+                        }
+                    ));
                 }
-            } else {
-                quote!(
-                    pub fn #i(&mut self, #i: #t) -> &mut Self {
-                        self.#i = Some(#i);
-                        self
-                    }
-                )
-            };
+            }
+            FieldKind::SubBuilder(_) => unreachable!("`sub_builder` is rejected with `typestate`"),
+            FieldKind::Required => {
+                // The state of required fields is in the builder type, they have no getters.
+                let state = &state_params[state_index];
+                field_vec.push(quote!(#i: #state));
+                empty_field_vec.push(quote!(#i: ()));
+                field_build_vec.push(quote!(#m: self.#i.0));
+                unset_states.push(quote!(()));
+                set_states.push(quote!((#t,)));
 
-            field_method_vec.push(method);
+                let other_params: Vec<Ident> = state_params
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| *index != state_index)
+                    .map(|(_, p)| p.clone())
+                    .collect();
+                let setter_generics = with_type_params(generics, &other_params);
+                let (setter_impl_generics, _, _) = setter_generics.split_for_impl();
+                let before = state_params.iter().enumerate().map(|(index, p)| {
+                    if index == state_index {
+                        quote!(())
+                    } else {
+                        quote!(#p)
+                    }
+                });
+                let after = state_params.iter().enumerate().map(|(index, p)| {
+                    if index == state_index {
+                        quote!((#t,))
+                    } else {
+                        quote!(#p)
+                    }
+                });
+                let (param_type, value) = field.setter_param(t);
+                let moved = fields.iter().map(|f| {
+                    let fi = &f.ident;
+                    if fi == i {
+                        quote!(#fi: (#value,))
+                    } else {
+                        quote!(#fi: self.#fi)
+                    }
+                });
+                // Fields without a default always have a setter.
+                let setter = field.setter.as_ref().unwrap();
+                state_method_vec.push(quote!(
+                    impl #setter_impl_generics #builder_ident<#(#struct_args,)* #(#before),*> #where_clause {
+                        #(#docs)*
+                        pub fn #setter(self, #i: #param_type) -> #builder_ident<#(#struct_args,)* #(#after),*> {
+                            #builder_ident {
+                                #(#moved,)*
+                                __marker: ::core::marker::PhantomData,
+                            }
+                        }
+                    }
+                ));
+                state_index += 1;
+            }
         }
-        empty_field_vec.push(quote!(
-            #i: None
-        ));
-        // eprintln!("named_field: {:#?}", named_field);
     }
 
-    // eprintln!("field_vec: {:#?}", field_vec);
+    let builder_attrs = opts.builder_attrs(false);
+    let build_name = opts
+        .build_fn
+        .name
+        .clone()
+        .unwrap_or_else(|| format_ident!("build"));
+    let build_vis = (!opts.build_fn.private).then(|| quote!(pub));
+    let builder_generics = with_type_params(generics, &state_params);
+    let (builder_impl_generics, _, _) = builder_generics.split_for_impl();
 
     quote!(
-        impl #ident {
-            pub fn builder() -> #builder_ident {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            pub fn #constructor() -> #builder_ident<#(#struct_args,)* #(#unset_states),*> {
                 #builder_ident {
                     #(#empty_field_vec,)*
+                    __marker: ::core::marker::PhantomData,
                 }
             }
         }
 
-        pub struct #builder_ident {
+        #builder_attrs
+        #vis struct #builder_ident #builder_impl_generics #where_clause {
             #(#field_vec,)*
+            // Required fields are stored as state parameters, keep the type parameters used.
+            __marker: ::core::marker::PhantomData<fn() -> #ident #ty_generics>,
+        }
+
+        impl #builder_impl_generics #builder_ident<#(#struct_args,)* #(#state_params),*> #where_clause {
+            #(#field_method_vec)*
         }
 
-        impl #builder_ident {
-            pub fn build(&mut self) -> std::result::Result<#ident, std::boxed::Box<dyn std::error::Error>> {
-                Ok(#ident{
+        #(#state_method_vec)*
+
+        impl #impl_generics #builder_ident<#(#struct_args,)* #(#set_states),*> #where_clause {
+            #build_vis fn #build_name(self) -> #ident #ty_generics {
+                #path {
                     #(#field_build_vec,)*
-                })
+                }
             }
-            #(#field_method_vec)*
         }
     )
-        .into()
+}
+
+/// Generate `to_builder()` and `From<T>` for the builder, creating a builder with every field
+/// set from an existing value.
+///
+/// The typestate builder is returned in the state where every required field is set.
+fn to_builder(
+    target: &Target,
+    fields: &[BuilderField],
+    typestate: bool,
+) -> proc_macro2::TokenStream {
+    let Target {
+        ident,
+        generics,
+        builder_ident,
+        ..
+    } = target;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let builder_type = if typestate {
+        let struct_args = generic_args(generics);
+        let set_states = fields
+            .iter()
+            .filter(|f| matches!(f.kind, FieldKind::Required) && f.default.is_none())
+            .map(|f| {
+                let t = f.ty;
+                quote!((#t,))
+            });
+        quote!(#builder_ident<#(#struct_args,)* #(#set_states),*>)
+    } else {
+        quote!(#builder_ident #ty_generics)
+    };
+    // Builder fields taking their value from `value(member)`, wrapped the way the builder stores
+    // them.
+    let populate = |value: &dyn Fn(&Member) -> proc_macro2::TokenStream| {
+        fields
+            .iter()
+            .map(|field| {
+                let i = &field.ident;
+                let v = value(&field.member);
+                match &field.kind {
+                    FieldKind::Optional(_) => quote!(#i: #v),
+                    FieldKind::SubBuilder(sub) => {
+                        quote!(#i: ::core::option::Option::Some(<#sub as ::core::convert::From<_>>::from(#v)))
+                    }
+                    FieldKind::Required if typestate && field.default.is_none() => {
+                        quote!(#i: (#v,))
+                    }
+                    FieldKind::Required | FieldKind::Each { .. } => {
+                        quote!(#i: ::core::option::Option::Some(#v))
+                    }
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let cloned = populate(&|m| quote!(::core::clone::Clone::clone(&self.#m)));
+    let moved = populate(&|m| quote!(value.#m));
+
+    quote!(
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Create a builder with every field set from this value.
+            pub fn to_builder(&self) -> #builder_type {
+                #builder_ident {
+                    #(#cloned,)*
+                    __marker: ::core::marker::PhantomData,
+                }
+            }
+        }
+
+        impl #impl_generics ::core::convert::From<#ident #ty_generics> for #builder_type #where_clause {
+            fn from(value: #ident #ty_generics) -> Self {
+                #builder_ident {
+                    #(#moved,)*
+                    __marker: ::core::marker::PhantomData,
+                }
+            }
+        }
+    )
+}
+
+/// Arguments to name a generic type with its own parameters, e.g. `'a, T, N` for
+/// `struct S<'a, T: Clone, const N: usize>`.
+fn generic_args(generics: &Generics) -> Vec<proc_macro2::TokenStream> {
+    generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Lifetime(v) => v.lifetime.to_token_stream(),
+            GenericParam::Type(v) => v.ident.to_token_stream(),
+            GenericParam::Const(v) => v.ident.to_token_stream(),
+        })
+        .collect()
+}
+
+/// Append extra type parameters, used for the typestate builder states.
+///
+/// Defaults on the original parameters are removed because they are only allowed in trailing
+/// positions.
+fn with_type_params(generics: &Generics, params: &[Ident]) -> Generics {
+    let mut generics = generics.clone();
+    for param in &mut generics.params {
+        match param {
+            GenericParam::Type(v) => {
+                v.eq_token = None;
+                v.default = None;
+            }
+            GenericParam::Const(v) => {
+                v.eq_token = None;
+                v.default = None;
+            }
+            GenericParam::Lifetime(_) => {}
+        }
+    }
+    for param in params {
+        generics.params.push(parse_quote!(#param));
+    }
+    generics
+}
+
+/// Get `T` out of a type written as one of `paths` with a single type argument, e.g.
+/// `Option<T>` or `::core::option::Option<T>`.
+///
+/// Only the tokens are checked, a type alias or a re-export under another name is not
+/// recognized.
+fn generic_inner<'a>(ty: &'a Type, paths: &[&[&str]]) -> Option<&'a Type> {
+    let ty = match ty {
+        // Types passed through `macro_rules!` may come wrapped in an invisible group.
+        Type::Group(v) => return generic_inner(&v.elem, paths),
+        Type::Paren(v) => return generic_inner(&v.elem, paths),
+        Type::Path(v) if v.qself.is_none() => v,
+        _ => return None,
+    };
+    let segments = &ty.path.segments;
+    let matched = paths.iter().any(|path| {
+        path.len() == segments.len()
+            && path.iter().zip(segments).all(|(p, s)| s.ident == p)
+            && segments
+                .iter()
+                .take(segments.len() - 1)
+                .all(|s| s.arguments.is_none())
+    });
+    if !matched {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segments.last()?.arguments else {
+        return None;
+    };
+    if args.args.len() != 1 {
+        return None;
+    }
+    match args.args.first()? {
+        GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
+/// Item type of a collection used with `each`.
+///
+/// Well known std collections are recognized by name, `(K, V)` for maps, anything else falls
+/// back to `<T as IntoIterator>::Item`, which matches the `Extend` item of std-like collections.
+fn collection_item(ty: &Type) -> EachItem {
+    if let Type::Path(path) = ty {
+        if let Some(last) = path.path.segments.last() {
+            if let PathArguments::AngleBracketed(args) = &last.arguments {
+                let types: Vec<&Type> = args
+                    .args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        GenericArgument::Type(v) => Some(v),
+                        _ => None,
+                    })
+                    .collect();
+                let name = last.ident.to_string();
+                if SEQUENCE_COLLECTIONS.contains(&name.as_str()) && !types.is_empty() {
+                    return EachItem::Single(types[0].clone());
+                }
+                if MAP_COLLECTIONS.contains(&name.as_str()) && types.len() >= 2 {
+                    return EachItem::Pair(types[0].clone(), types[1].clone());
+                }
+            }
+        }
+    }
+    EachItem::Single(parse_quote!(<#ty as ::core::iter::IntoIterator>::Item))
+}
+
+/// Parameter type of an `each` setter and the expression turning the parameter into an item.
+///
+/// With `into`, map setters take `(impl Into<K>, impl Into<V>)` because tuples have no `Into`
+/// conversion between their element types.
+fn each_param(
+    setter: &Ident,
+    item: &EachItem,
+    into: bool,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    match (item, into) {
+        (EachItem::Single(item), false) => (quote!(#item), quote!(#setter)),
+        (EachItem::Single(item), true) => (
+            quote!(impl ::core::convert::Into<#item>),
+            quote!(::core::convert::Into::into(#setter)),
+        ),
+        (EachItem::Pair(k, v), false) => (quote!((#k, #v)), quote!(#setter)),
+        (EachItem::Pair(k, v), true) => (
+            quote!((impl ::core::convert::Into<#k>, impl ::core::convert::Into<#v>)),
+            quote!((
+                ::core::convert::Into::into(#setter.0),
+                ::core::convert::Into::into(#setter.1),
+            )),
+        ),
+    }
+}
+
+/// Statement of `from_env()` reading `field` into `builder`, `None` for fields without setter
+/// or with `env(skip)`.
+fn env_statement(
+    field: &BuilderField,
+    separator: &str,
+    error_ident: &Ident,
+) -> Option<proc_macro2::TokenStream> {
+    if field.env.as_ref().is_some_and(|v| v.skip) {
+        return None;
+    }
+    let i = &field.ident;
+    let variable = if let Some(name) = field.env.as_ref().and_then(|v| v.name.as_ref()) {
+        quote!(::std::string::String::from(#name))
+    } else {
+        let name = i.unraw().to_string().to_uppercase();
+        quote!(if prefix.is_empty() {
+            ::std::string::String::from(#name)
+        } else {
+            ::std::format!("{}_{}", prefix, #name)
+        })
+    };
+    let value = match &field.kind {
+        // Spanned on the type, which is reported when it does not implement `FromStr`.
+        FieldKind::Required => {
+            field.setter.as_ref()?;
+            let t = field.ty;
+            quote_spanned!(t.span()=> parse::<#t>(&variable, &value)?)
+        }
+        FieldKind::Optional(t) => {
+            field.setter.as_ref()?;
+            quote_spanned!(t.span()=> parse::<#t>(&variable, &value)?)
+        }
+        // Sub-builders are filled through their own `from_env()`, with `PREFIX_FIELD` as prefix.
+        FieldKind::SubBuilder(sub) => {
+            let name = ident_token_str!(i);
+            let from_env = quote_spanned!(sub.span()=> <#sub>::from_env(&variable));
+            return Some(quote!({
+                let variable = #variable;
+                match #from_env {
+                    ::core::result::Result::Ok(sub) => {
+                        builder.#i = ::core::option::Option::Some(sub);
+                    }
+                    ::core::result::Result::Err(e) => {
+                        let path = match e.field_path() {
+                            ::core::option::Option::Some(path) => ::std::format!("{}.{}", #name, path),
+                            ::core::option::Option::None => ::std::string::ToString::to_string(#name),
+                        };
+                        return ::core::result::Result::Err(#error_ident::Nested {
+                            path,
+                            message: e.__nested_message(),
+                        });
+                    }
+                }
+            }));
+        }
+        FieldKind::Each { setter, item, .. } => {
+            setter.as_ref()?;
+            let separator = field
+                .env
+                .as_ref()
+                .and_then(|v| v.separator.as_deref())
+                .unwrap_or(separator);
+            let item = match item {
+                EachItem::Single(t) => quote_spanned!(t.span()=> parse::<#t>(&variable, item)?),
+                // Map entries are written as `key=value`.
+                EachItem::Pair(k, v) => quote!({
+                    let (key, val) = ::core::option::Option::ok_or_else(item.split_once('='), || {
+                        #error_ident::InvalidEnv {
+                            variable: ::core::clone::Clone::clone(&variable),
+                            message: ::std::string::String::from("expected `key=value`"),
+                        }
+                    })?;
+                    (parse::<#k>(&variable, key)?, parse::<#v>(&variable, val)?)
+                }),
+            };
+            let t = field.ty;
+            quote!({
+                let mut items: #t = ::core::default::Default::default();
+                for item in ::core::iter::Iterator::filter(value.split(#separator), |item| !item.is_empty()) {
+                    ::core::iter::Extend::extend(&mut items, ::core::iter::once(#item));
+                }
+                items
+            })
+        }
+    };
+    Some(quote!({
+        let variable = #variable;
+        if let ::core::option::Option::Some(value) = read(&variable)? {
+            builder.#i = ::core::option::Option::Some(#value);
+        }
+    }))
+}
+
+/// Names of the methods extending and clearing an `each` collection.
+fn each_extend_clear(field: &Ident) -> (Ident, Ident) {
+    (
+        format_ident!("extend_{}", field.unraw()),
+        format_ident!("clear_{}", field.unraw()),
+    )
+}
+
+/// Builder of a `#[builder(sub_builder)]` field, `Inner<T>` is built by `InnerBuilder<T>`.
+///
+/// `Option` and std collections are rejected, they do not derive `Builder`.
+fn sub_builder_type(ty: &Type) -> Option<Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    if path.qself.is_some() || generic_inner(ty, OPTION_PATHS).is_some() {
+        return None;
+    }
+    let mut path = path.clone();
+    let last = path.path.segments.last_mut()?;
+    let name = last.ident.to_string();
+    if SEQUENCE_COLLECTIONS.contains(&name.as_str()) || MAP_COLLECTIONS.contains(&name.as_str()) {
+        return None;
+    }
+    last.ident = format_ident!("{}Builder", last.ident);
+    Some(Type::Path(path))
+}
+
+/// Turn a `CamelCase` variant name into `snake_case`, a run of capitals like `HTTPRequest`
+/// being one word: `http_request`.
+fn snake_case(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            let prev = chars[index - 1];
+            let next_lower = chars.get(index + 1).is_some_and(|v| v.is_lowercase());
+            if (!prev.is_uppercase() && prev != '_') || (prev.is_uppercase() && next_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
 }
@@ -0,0 +1,47 @@
+// Calling build() before every required field is set should fail instead of
+// silently filling the gaps with Default values.
+//
+// The macro generates a `{Name}BuilderError` enum next to the builder. Its
+// `MissingFields` variant lists every required field that is still unset, in
+// declaration order, so the caller can report all of them at once. Fields with
+// `each` and `Option` fields are never reported.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    #[builder(each = "env")]
+    env: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let err = Command::builder().build().err().unwrap();
+    assert_eq!(
+        err,
+        CommandBuilderError::MissingFields(vec!["executable", "args"])
+    );
+    assert_eq!(
+        err.to_string(),
+        "missing required field(s): `executable`, `args`"
+    );
+
+    let err = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err, CommandBuilderError::MissingFields(vec!["args"]));
+
+    let boxed: Box<dyn std::error::Error> = Box::new(err);
+    assert_eq!(boxed.to_string(), "missing required field(s): `args`");
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .args(vec![])
+        .build()
+        .unwrap();
+    assert!(command.env.is_empty());
+}
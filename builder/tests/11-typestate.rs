@@ -0,0 +1,41 @@
+// With #[builder(typestate)] the builder records in its type which required
+// fields have been set, so the checks done by build() at runtime move to
+// compile time.
+//
+// Required fields have no Option in the builder at all. Setters consume the
+// builder and return it in a new state, and build() exists only once every
+// required field is set, returning the struct directly instead of a Result.
+// Option fields and `each` fields can be set in any state.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    #[builder(each = "env")]
+    env: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .current_dir("..".to_owned())
+        .args(vec!["build".to_owned(), "--release".to_owned()])
+        .env("RUST_LOG=info".to_owned())
+        .executable("cargo".to_owned())
+        .build();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.env, vec!["RUST_LOG=info"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .args(vec![])
+        .build();
+    assert!(command.env.is_empty());
+    assert!(command.current_dir.is_none());
+}
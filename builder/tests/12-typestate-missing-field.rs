@@ -0,0 +1,19 @@
+// In typestate mode a builder that is missing a required field has no build()
+// method, so the mistake is reported by the compiler.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("..".to_owned())
+        .build();
+}
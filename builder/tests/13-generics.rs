@@ -0,0 +1,63 @@
+// The builder carries over the generic parameters of the struct: lifetimes,
+// type parameters, const generics and the where clause all end up on the
+// builder type, the builder() constructor and the generated impls, in both the
+// default and the typestate mode. Defaults of type parameters carry over to
+// the default builder, so `PageBuilder` names `PageBuilder<u8>`.
+
+use derive_builder::Builder;
+use std::fmt::Debug;
+
+#[derive(Builder)]
+pub struct Request<'a, T: Clone, const N: usize>
+where
+    T: Debug,
+{
+    path: &'a str,
+    body: T,
+    headers: [u8; N],
+    timeout: Option<u32>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Response<'a, T, const N: usize = 4>
+where
+    T: Debug,
+{
+    status: &'a str,
+    body: T,
+    trailer: [u8; N],
+    #[builder(each = "header")]
+    headers: Vec<T>,
+}
+
+#[derive(Builder)]
+pub struct Page<T: Clone = u8> {
+    items: Vec<T>,
+}
+
+fn main() {
+    let path = String::from("/index.html");
+    let request = Request::<Vec<u8>, 2>::builder()
+        .path(&path)
+        .body(vec![1, 2, 3])
+        .headers([4, 5])
+        .build()
+        .unwrap();
+    assert_eq!(request.path, "/index.html");
+    assert_eq!(request.body, vec![1, 2, 3]);
+    assert!(request.timeout.is_none());
+
+    let response: Response<String> = Response::builder()
+        .trailer([0; 4])
+        .status("ok")
+        .header("a".to_owned())
+        .body("hello".to_owned())
+        .build();
+    assert_eq!(response.status, "ok");
+    assert_eq!(response.headers, vec!["a"]);
+
+    let mut page: PageBuilder = Page::builder();
+    let page = page.items(vec![1, 2]).build().unwrap();
+    assert_eq!(page.items, [1, 2]);
+}
@@ -0,0 +1,61 @@
+// Required fields can opt into a fallback value with #[builder(default)],
+// which uses Default::default(), or #[builder(default = ...)] with either a
+// string containing an expression or the expression itself. Only fields
+// without any default are reported by build() when they are unset, and a
+// field with a default does not need its type to implement Default unless it
+// uses the bare form.
+
+use derive_builder::Builder;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Port(u16);
+
+fn default_workers() -> usize {
+    4
+}
+
+#[derive(Builder)]
+pub struct Server {
+    host: String,
+    #[builder(default = Port(8080))]
+    port: Port,
+    #[builder(default = "default_workers() * 2")]
+    workers: usize,
+    #[builder(default)]
+    verbose: bool,
+    #[builder(default = Some("/tmp".to_owned()))]
+    root: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Client {
+    host: String,
+    #[builder(default = "Port(443)")]
+    port: Port,
+}
+
+fn main() {
+    let server = Server::builder().host("localhost".to_owned()).build().unwrap();
+    assert_eq!(server.port, Port(8080));
+    assert_eq!(server.workers, 8);
+    assert!(!server.verbose);
+    assert_eq!(server.root.as_deref(), Some("/tmp"));
+
+    let server = Server::builder()
+        .host("localhost".to_owned())
+        .port(Port(80))
+        .verbose(true)
+        .root("/srv".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(server.port, Port(80));
+    assert!(server.verbose);
+    assert_eq!(server.root.as_deref(), Some("/srv"));
+
+    let err = Server::builder().build().err().unwrap();
+    assert_eq!(err, ServerBuilderError::MissingFields(vec!["host"]));
+
+    let client = Client::builder().host("example.com".to_owned()).build();
+    assert_eq!(client.port, Port(443));
+}
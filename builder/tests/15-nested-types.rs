@@ -0,0 +1,42 @@
+// Option and Vec are found by looking at the type syntax tree rather than its
+// string form, so the inner type may be anything: nested generics, paths,
+// references or tuples. Fully qualified std::option::Option and
+// core::option::Option are recognized too, as are std::vec::Vec and
+// alloc::vec::Vec for `each`.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command<'a> {
+    executable: &'a str,
+    args: Option<Vec<String>>,
+    current_dir: Option<std::path::PathBuf>,
+    label: std::option::Option<&'a str>,
+    priority: ::core::option::Option<(u8, u8)>,
+    #[builder(each = "pair")]
+    pairs: Vec<(u8, u8)>,
+    #[builder(each = "env")]
+    env: std::vec::Vec<Option<String>>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo")
+        .args(vec!["build".to_owned()])
+        .current_dir(std::path::PathBuf::from(".."))
+        .label("release")
+        .priority((1, 2))
+        .pair((3, 4))
+        .pair((5, 6))
+        .env(None)
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, Some(vec!["build".to_owned()]));
+    assert_eq!(command.current_dir, Some(std::path::PathBuf::from("..")));
+    assert_eq!(command.label, Some("release"));
+    assert_eq!(command.priority, Some((1, 2)));
+    assert_eq!(command.pairs, vec![(3, 4), (5, 6)]);
+    assert_eq!(command.env, vec![None]);
+}
@@ -0,0 +1,74 @@
+// `each` is not limited to Vec. Any collection implementing Default and
+// Extend<Item> works: maps take `(K, V)` pairs, and collections that are not
+// known by name use their IntoIterator item type.
+//
+// The long form #[builder(each(name = "...", into))] makes the per-item setter
+// accept anything convertible into the item type. For maps the key and the
+// value are converted separately.
+
+use derive_builder::Builder;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+#[derive(Default, Clone)]
+pub struct Tags(Vec<String>);
+
+impl Extend<String> for Tags {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for Tags {
+    type Item = String;
+    type IntoIter = std::vec::IntoIter<String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[derive(Builder)]
+pub struct Service {
+    #[builder(each(name = "env", into))]
+    env: HashMap<String, String>,
+    #[builder(each = "port")]
+    ports: BTreeSet<u32>,
+    #[builder(each(name = "step", into))]
+    steps: VecDeque<String>,
+    #[builder(each = "tag")]
+    tags: Tags,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Job {
+    name: String,
+    #[builder(each = "env")]
+    env: std::collections::BTreeMap<String, u32>,
+}
+
+fn main() {
+    let service = Service::builder()
+        .env(("RUST_LOG", "info"))
+        .env(("HOME".to_owned(), "/root".to_owned()))
+        .port(8080)
+        .port(80)
+        .port(8080)
+        .step("fetch")
+        .step("build".to_owned())
+        .tag("web".to_owned())
+        .build()
+        .unwrap_or_else(|_| unreachable!());
+
+    assert_eq!(service.env["RUST_LOG"], "info");
+    assert_eq!(service.env["HOME"], "/root");
+    assert_eq!(service.ports.into_iter().collect::<Vec<_>>(), vec![80, 8080]);
+    assert_eq!(service.steps, vec!["fetch", "build"]);
+    assert_eq!(service.tags.0, vec!["web"]);
+
+    let job = Job::builder()
+        .env(("THREADS".to_owned(), 4))
+        .name("test".to_owned())
+        .build();
+    assert_eq!(job.env["THREADS"], 4);
+}
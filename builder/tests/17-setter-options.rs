@@ -0,0 +1,77 @@
+// Setters can be tuned per field through #[builder(setter(...))]:
+//
+//   - `into` makes the setter generic over `impl Into<T>`, for Option fields
+//     over the inner type.
+//   - `name = "..."` renames the setter.
+//   - `skip` generates no setter at all, the field then always takes its
+//     `default`, or Default::default() when none is given.
+//
+// On the struct, #[builder(setter(prefix = "..."))] prepends a prefix to every
+// setter named after its field. Explicit names from `setter(name = ...)` and
+// `each` are used as they are.
+//
+// There is no `strip_option`: setters of Option fields always take the inner
+// type. Raw field names keep a raw setter, `r#type` gets `r#type()` without a
+// prefix and `with_type()` with one.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(setter(prefix = "with_"))]
+pub struct Command {
+    #[builder(setter(into))]
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(setter(into, name = "cwd"))]
+    current_dir: Option<String>,
+    #[builder(setter(skip))]
+    pid: u32,
+    #[builder(setter(skip), default = "\"cargo\".len()")]
+    len: usize,
+    r#type: u8,
+}
+
+#[derive(Builder)]
+pub struct Token {
+    r#type: u8,
+    r#match: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Job {
+    #[builder(setter(into, name = "named"))]
+    name: String,
+    #[builder(setter(skip))]
+    attempts: u32,
+}
+
+fn main() {
+    let command = Command::builder()
+        .with_executable("cargo")
+        .arg("build".to_owned())
+        .cwd("..")
+        .with_type(1)
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+    assert_eq!(command.pid, 0);
+    assert_eq!(command.len, 5);
+    assert_eq!(command.r#type, 1);
+
+    let token = Token::builder()
+        .r#type(2)
+        .r#match("*".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(token.r#type, 2);
+    assert_eq!(token.r#match.as_deref(), Some("*"));
+
+    let job = Job::builder().named("test").build();
+    assert_eq!(job.name, "test");
+    assert_eq!(job.attempts, 0);
+}
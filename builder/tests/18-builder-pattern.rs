@@ -0,0 +1,57 @@
+// #[builder(pattern = "...")] selects how the builder is passed around:
+//
+//   - "mutable" (the default): setters take `&mut self` and return `&mut Self`,
+//     build() clones every field out of the builder.
+//   - "owned": setters and build() consume the builder, so fields are moved
+//     instead of cloned and do not need to implement Clone.
+//   - "immutable": setters take `&self` and return an updated copy, leaving
+//     the original builder untouched.
+
+use derive_builder::Builder;
+use std::fmt::Display;
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Logger {
+    sink: Box<dyn Display>,
+    #[builder(each = "tag")]
+    tags: Vec<String>,
+    level: Option<u8>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Request {
+    url: String,
+    retries: Option<u32>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "mutable")]
+pub struct Response {
+    status: u16,
+}
+
+fn main() {
+    let logger = Logger::builder()
+        .sink(Box::new("stderr"))
+        .tag("a".to_owned())
+        .level(3)
+        .build()
+        .unwrap();
+    assert_eq!(logger.sink.to_string(), "stderr");
+    assert_eq!(logger.tags, vec!["a"]);
+    assert_eq!(logger.level, Some(3));
+
+    let base = Request::builder().url("http://localhost".to_owned());
+    let with_retries = base.retries(3);
+    let request = base.build().unwrap();
+    assert_eq!(request.url, "http://localhost");
+    assert_eq!(request.retries, None);
+    let request = with_retries.build().unwrap();
+    assert_eq!(request.retries, Some(3));
+
+    let mut builder = Response::builder();
+    builder.status(200);
+    assert_eq!(builder.build().unwrap().status, 200);
+}
@@ -0,0 +1,93 @@
+// Validation hooks let build() reject values that are set but not acceptable.
+//
+// A field level #[builder(validate = "path")] calls `path(&field)` and a
+// struct level #[builder(validate = "path")] calls `path(&value)` once the
+// struct has been assembled. Both functions return `Result<(), E>` where `E`
+// implements Display, and a failure is reported through the generated
+// builder error: `InvalidField { field, message }` for fields, `Invalid` for
+// the struct. Field checks run first, in declaration order.
+
+use derive_builder::Builder;
+
+fn non_empty(value: &str) -> Result<(), &'static str> {
+    if value.is_empty() {
+        Err("must not be empty")
+    } else {
+        Ok(())
+    }
+}
+
+mod checks {
+    pub fn port(port: &u16) -> Result<(), String> {
+        if *port < 1024 {
+            Err(format!("{} is a privileged port", port))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn range(server: &super::Server) -> Result<(), std::fmt::Error> {
+        if server.workers > server.max_workers {
+            Err(std::fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Builder)]
+#[builder(validate = checks::range)]
+pub struct Server {
+    #[builder(validate = "non_empty")]
+    host: String,
+    #[builder(validate = "checks::port", default = 8080)]
+    port: u16,
+    workers: usize,
+    max_workers: usize,
+}
+
+fn main() {
+    let server = Server::builder()
+        .host("localhost".to_owned())
+        .workers(2)
+        .max_workers(4)
+        .build()
+        .unwrap();
+    assert_eq!(server.port, 8080);
+
+    let err = Server::builder()
+        .host(String::new())
+        .port(80)
+        .workers(2)
+        .max_workers(4)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ServerBuilderError::InvalidField {
+            field: "host",
+            message: "must not be empty".to_owned(),
+        }
+    );
+    assert_eq!(err.to_string(), "invalid value for `host`: must not be empty");
+
+    let err = Server::builder()
+        .host("localhost".to_owned())
+        .port(80)
+        .workers(2)
+        .max_workers(4)
+        .build()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "invalid value for `port`: 80 is a privileged port");
+
+    let err = Server::builder()
+        .host("localhost".to_owned())
+        .workers(8)
+        .max_workers(4)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ServerBuilderError::Invalid("an error occurred when formatting an argument".to_owned())
+    );
+}
@@ -0,0 +1,78 @@
+// Tuple structs and enums can derive Builder too.
+//
+// Tuple fields get positional setters `_0`, `_1`, ... unless a field is named
+// with #[builder(name = "...")], which is also the name reported by build()
+// when the field is missing.
+//
+// Enums get one builder per variant: `Message::text_builder()` returns a
+// `MessageTextBuilder` whose build() produces `Message::Text { .. }`, with its
+// own `MessageTextBuilderError`. Unit variants get a builder without setters.
+// A run of capitals is one word, `HTTPRequest` gets `http_request_builder()`.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Point(i32, #[builder(name = "y")] i32, Option<String>);
+
+#[derive(Debug, PartialEq, Builder)]
+pub enum Message<T: Clone> {
+    Text {
+        body: String,
+        #[builder(each = "tag")]
+        tags: Vec<String>,
+    },
+    Binary(T, #[builder(default)] u8),
+    Ping,
+    HTTPRequest {
+        url: String,
+    },
+}
+
+#[derive(Debug, PartialEq, Builder)]
+#[builder(typestate)]
+pub enum Shape {
+    Circle { radius: u32 },
+    Rect(u32, u32),
+}
+
+fn main() {
+    let point = Point::builder()._0(1).y(2)._2("origin".to_owned()).build().unwrap();
+    assert_eq!((point.0, point.1, point.2.as_deref()), (1, 2, Some("origin")));
+
+    let err = Point::builder()._0(1).build().err().unwrap();
+    assert_eq!(err, PointBuilderError::MissingFields(vec!["y"]));
+
+    let text: Message<u8> = Message::text_builder()
+        .body("hello".to_owned())
+        .tag("greeting".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(
+        text,
+        Message::Text {
+            body: "hello".to_owned(),
+            tags: vec!["greeting".to_owned()],
+        }
+    );
+
+    let binary = Message::binary_builder()._0(vec![1u8, 2]).build().unwrap();
+    assert_eq!(binary, Message::Binary(vec![1, 2], 0));
+
+    let err = Message::<u8>::binary_builder().build().unwrap_err();
+    assert_eq!(err, MessageBinaryBuilderError::MissingFields(vec!["_0"]));
+
+    let request = Message::<()>::http_request_builder()
+        .url("/".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(request, Message::HTTPRequest { url: "/".to_owned() });
+
+    let ping = Message::<()>::ping_builder().build().unwrap();
+    assert_eq!(ping, Message::Ping);
+
+    assert_eq!(
+        Shape::circle_builder().radius(3).build(),
+        Shape::Circle { radius: 3 }
+    );
+    assert_eq!(Shape::rect_builder()._1(2)._0(1).build(), Shape::Rect(1, 2));
+}
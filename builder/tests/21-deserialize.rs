@@ -0,0 +1,37 @@
+// With the `serde` feature enabled, #[builder(derive(Deserialize))] makes the
+// builder itself deserializable so a config file can be loaded into it.
+//
+// The builder is a partial layer: keys missing from the input leave their
+// field unset, setters can fill in or override values afterwards, and build()
+// still reports whatever is required but missing. Each fields deserialize
+// from a list and default to empty.
+//
+// The crate using the derive needs its own dependency on serde.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(derive(Deserialize))]
+pub struct Config {
+    host: String,
+    port: u16,
+    user: Option<String>,
+    #[builder(each = "tag")]
+    tags: Vec<String>,
+}
+
+fn main() {
+    let mut builder: ConfigBuilder = serde_json::from_str(r#"{ "host": "localhost" }"#).unwrap();
+    match builder.build() {
+        Err(ConfigBuilderError::MissingFields(fields)) => assert_eq!(fields, ["port"]),
+        other => panic!("unexpected {:?}", other.map(|_| ())),
+    }
+
+    let mut builder: ConfigBuilder =
+        serde_json::from_str(r#"{ "host": "localhost", "tags": ["a"] }"#).unwrap();
+    let config = builder.port(8080).tag("b".to_owned()).build().unwrap();
+    assert_eq!(config.host, "localhost");
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.user, None);
+    assert_eq!(config.tags, ["a", "b"]);
+}
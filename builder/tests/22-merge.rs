@@ -0,0 +1,42 @@
+// Builders can be layered with merge(), e.g. defaults, then a config file,
+// then environment variables, then command line flags.
+//
+// `a.merge(b)` takes every field set in `b` and keeps the value of `a` for
+// the fields `b` left unset. Collections filled through `each` setters are
+// appended to by default, #[builder(merge = "replace")] uses the collection of
+// the later builder instead when it set any item.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env", merge = "replace")]
+    env: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let mut defaults = Command::builder();
+    defaults
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .env("RUST_LOG=info".to_owned())
+        .current_dir("/".to_owned());
+
+    let mut file = Command::builder();
+    file.arg("--release".to_owned());
+
+    let mut flags = Command::builder();
+    flags
+        .executable("cross".to_owned())
+        .env("RUST_LOG=debug".to_owned());
+
+    let command = defaults.merge(file).merge(flags).build().unwrap();
+    assert_eq!(command.executable, "cross");
+    assert_eq!(command.args, ["build", "--release"]);
+    assert_eq!(command.env, ["RUST_LOG=debug"]);
+    assert_eq!(command.current_dir.as_deref(), Some("/"));
+}
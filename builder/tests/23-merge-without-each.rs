@@ -0,0 +1,13 @@
+// #[builder(merge = "...")] only applies to `each` collections, every other
+// field is simply replaced when the later builder set it.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(merge = "append")]
+    args: Vec<String>,
+}
+
+fn main() {}
@@ -0,0 +1,101 @@
+// #[builder(from_env)] generates `NameBuilder::from_env(prefix)`, which fills
+// the builder from environment variables.
+//
+// Each field is read from `PREFIX_FIELD` and parsed with FromStr, unset
+// variables leave the field unset. #[builder(env = "NAME")] reads the field
+// from `NAME` instead, without prefix. Collections filled through `each`
+// setters are split on the separator given as
+// #[builder(from_env(separator = "..."))], "," by default, or as
+// #[builder(env(separator = "..."))] on the field. Map entries are written as
+// `key=value`.
+//
+// #[builder(sub_builder)] fields are read with the `from_env()` of their
+// builder, using `PREFIX_FIELD` as prefix, so their type needs
+// #[builder(from_env)] too. Their failures are reported as `Nested` errors.
+//
+// Fields whose type does not implement FromStr, like a collection without
+// `each`, are left out with #[builder(env(skip))] and set through setters.
+//
+// A value that does not parse is reported as `InvalidEnv` naming the
+// variable.
+
+use derive_builder::Builder;
+use std::collections::HashMap;
+
+#[derive(Builder, Debug)]
+#[builder(from_env)]
+pub struct Server {
+    host: String,
+    port: u16,
+    workers: Option<usize>,
+    #[builder(env = "SERVER_LOG_LEVEL")]
+    log_level: String,
+    #[builder(each = "origin")]
+    origins: Vec<String>,
+    #[builder(each = "path", env(separator = ":"))]
+    paths: Vec<String>,
+    #[builder(each = "label")]
+    labels: HashMap<String, u32>,
+    #[builder(env(skip), default)]
+    tags: Vec<String>,
+}
+
+#[derive(Builder, Debug)]
+#[builder(from_env)]
+pub struct Service {
+    name: String,
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+fn main() {
+    std::env::set_var("APP_HOST", "localhost");
+    std::env::set_var("APP_PORT", "8080");
+    std::env::set_var("SERVER_LOG_LEVEL", "debug");
+    std::env::set_var("APP_ORIGINS", "a.example,b.example");
+    std::env::set_var("APP_PATHS", "/usr/bin:/bin");
+    std::env::set_var("APP_LABELS", "tier=1,zone=2");
+    std::env::set_var("APP_TAGS", "ignored");
+
+    let server = ServerBuilder::from_env("APP").unwrap().build().unwrap();
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.workers, None);
+    assert_eq!(server.log_level, "debug");
+    assert_eq!(server.origins, ["a.example", "b.example"]);
+    assert_eq!(server.paths, ["/usr/bin", "/bin"]);
+    assert_eq!(server.labels["zone"], 2);
+    assert!(server.tags.is_empty());
+
+    // Variables can be combined with setters.
+    std::env::remove_var("APP_PORT");
+    let server = ServerBuilder::from_env("APP")
+        .unwrap()
+        .port(9090)
+        .build()
+        .unwrap();
+    assert_eq!(server.port, 9090);
+
+    std::env::set_var("SVC_NAME", "api");
+    std::env::set_var("SVC_SERVER_HOST", "api.example");
+    std::env::set_var("SVC_SERVER_PORT", "443");
+    let service = ServiceBuilder::from_env("SVC").unwrap().build().unwrap();
+    assert_eq!(service.name, "api");
+    assert_eq!(service.server.host, "api.example");
+    assert_eq!(service.server.port, 443);
+
+    std::env::set_var("SVC_SERVER_PORT", "https");
+    match ServiceBuilder::from_env("SVC") {
+        Err(ServiceBuilderError::Nested { path, message }) => {
+            assert_eq!(path, "server");
+            assert!(message.contains("SVC_SERVER_PORT"));
+        }
+        other => panic!("unexpected {:?}", other.map(|_| ())),
+    }
+
+    std::env::set_var("APP_WORKERS", "many");
+    match ServerBuilder::from_env("APP") {
+        Err(ServerBuilderError::InvalidEnv { variable, .. }) => assert_eq!(variable, "APP_WORKERS"),
+        other => panic!("unexpected {:?}", other.map(|_| ())),
+    }
+}
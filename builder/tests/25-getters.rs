@@ -0,0 +1,74 @@
+// The builder can be inspected before build(), e.g. to show which steps of a
+// form are still missing.
+//
+// Every field gets a getter returning `Option<&T>`, named `get_{field}` by
+// default so it does not clash with the setter, #[builder(getter(prefix =
+// "..."))] picks another prefix. `is_{field}_set()` tells whether the field
+// has been set and missing_fields() lists the required fields without default
+// that build() would report as missing.
+//
+// The typestate builder has getters and `is_{field}_set()` for optional,
+// `each` and defaulted fields. Required fields are tracked in the builder type
+// instead, so they have neither getters nor missing_fields().
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(default)]
+    verbose: bool,
+}
+
+#[derive(Builder)]
+#[builder(getter(prefix = "current_"))]
+pub struct Page {
+    title: String,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Job {
+    name: String,
+    retries: Option<u32>,
+    #[builder(each = "tag")]
+    tags: Vec<String>,
+    #[builder(default = "1")]
+    priority: u8,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    assert_eq!(builder.missing_fields(), ["executable"]);
+    assert!(!builder.is_executable_set());
+    assert_eq!(builder.get_executable(), None);
+    assert_eq!(builder.get_args(), None);
+
+    builder
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .current_dir("..".to_owned());
+    assert!(builder.missing_fields().is_empty());
+    assert!(builder.is_executable_set());
+    assert!(!builder.is_verbose_set());
+    assert_eq!(builder.get_executable().map(String::as_str), Some("cargo"));
+    assert_eq!(builder.get_args().map(Vec::len), Some(1));
+    assert_eq!(builder.get_current_dir().map(String::as_str), Some(".."));
+    assert_eq!(builder.get_verbose(), None);
+
+    let mut page = Page::builder();
+    page.title("Home".to_owned());
+    assert_eq!(page.current_title().map(String::as_str), Some("Home"));
+
+    let job = Job::builder();
+    assert!(!job.is_retries_set());
+    assert_eq!(job.get_tags(), None);
+    let job = job.retries(3).tag("nightly".to_owned()).name("lint".to_owned());
+    assert_eq!(job.get_retries(), Some(&3));
+    assert_eq!(job.get_tags().map(Vec::len), Some(1));
+    assert!(!job.is_priority_set());
+    assert_eq!(job.build().priority, 1);
+}
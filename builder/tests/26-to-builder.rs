@@ -0,0 +1,54 @@
+// #[builder(to_builder)] goes the other way round: `value.to_builder()` and
+// `NameBuilder::from(value)` create a builder with every field set from an
+// existing value, to derive modified copies of it.
+//
+// to_builder() clones the fields, From moves them. A typestate builder is
+// returned in the state where every required field is set, so it can be
+// built right away. Every field type needs to be Clone for to_builder().
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(to_builder)]
+pub struct Config {
+    host: String,
+    port: u16,
+    user: Option<String>,
+    #[builder(each = "tag")]
+    tags: Vec<String>,
+    #[builder(default = 4)]
+    workers: usize,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(typestate, to_builder)]
+pub struct Point<T: Clone> {
+    x: T,
+    y: T,
+    label: Option<String>,
+}
+
+fn main() {
+    let base = Config::builder()
+        .host("localhost".to_owned())
+        .port(80)
+        .tag("a".to_owned())
+        .workers(8)
+        .build()
+        .unwrap();
+
+    let copy = base.to_builder().port(8080).tag("b".to_owned()).build().unwrap();
+    assert_eq!(copy.host, "localhost");
+    assert_eq!(copy.port, 8080);
+    assert_eq!(copy.user, None);
+    assert_eq!(copy.tags, ["a", "b"]);
+    assert_eq!(copy.workers, 8);
+
+    let same = ConfigBuilder::from(base).build().unwrap();
+    assert_eq!(same.port, 80);
+
+    let point = Point::builder().x(1).y(2).build();
+    let moved = point.to_builder().label("p".to_owned()).build();
+    assert_eq!(moved, Point { x: 1, y: 2, label: Some("p".to_owned()) });
+    assert_eq!(PointBuilder::from(point).build().x, 1);
+}
@@ -0,0 +1,55 @@
+// The builder type can be adjusted from the struct attributes:
+//
+//   #[builder(vis = "pub(crate)")]   visibility of the builder and its error,
+//                                    the visibility of the struct by default
+//   #[builder(name = "...")]         name of the builder, the error type is
+//                                    named after it
+//   #[builder(derive(Debug, ...))]   traits derived on the builder
+//   #[builder(attr(...))]            any other attribute put on the builder
+//
+// Doc comments on fields are copied onto their setters.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder, Debug, Clone, PartialEq)]
+    #[builder(
+        vis = "pub(crate)",
+        name = "ServerConfigBuilder",
+        derive(Debug, Clone, PartialEq),
+        attr(must_use)
+    )]
+    pub struct Config {
+        /// Host name to listen on.
+        pub host: String,
+        /// Port to listen on.
+        pub port: u16,
+    }
+
+    // Private types get a private builder unless told otherwise.
+    #[derive(Builder)]
+    struct Secret {
+        value: String,
+    }
+
+    pub fn secret() -> String {
+        let mut secret: SecretBuilder = Secret::builder();
+        secret.value("hunter2".to_owned()).build().unwrap().value
+    }
+}
+
+use config::{ServerConfigBuilder, ServerConfigBuilderError};
+
+fn main() {
+    let mut builder: ServerConfigBuilder = config::Config::builder();
+    builder.host("localhost".to_owned());
+
+    let copy = builder.clone();
+    assert_eq!(copy, builder);
+    assert!(format!("{:?}", builder).starts_with("ServerConfigBuilder"));
+
+    let err: ServerConfigBuilderError = builder.build().unwrap_err();
+    assert_eq!(err, ServerConfigBuilderError::MissingFields(vec!["port"]));
+
+    assert_eq!(config::secret(), "hunter2");
+}
@@ -0,0 +1,55 @@
+// #[builder(no_std)] lets the builder be used in `no_std` crates with `alloc`.
+//
+// The generated code only refers to `::core` and `::alloc`, every path is
+// fully qualified so it does not depend on the prelude, and the error type is
+// a plain enum rather than a boxed trait object. The deriving crate needs
+// `extern crate alloc;`. `from_env` reads `std::env` and is not available.
+//
+// This test links std under another name only to get a runtime for main(),
+// so the generated code can not reach it as `::std`.
+
+#![no_std]
+
+extern crate alloc;
+extern crate std as real_std;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(no_std, pattern = "immutable", to_builder)]
+pub struct Packet {
+    id: u32,
+    #[builder(each = "byte")]
+    payload: Vec<u8>,
+    label: Option<String>,
+    #[builder(default, validate = "check_ttl")]
+    ttl: u8,
+}
+
+fn check_ttl(ttl: &u8) -> Result<(), &'static str> {
+    if *ttl > 64 {
+        Err("too large")
+    } else {
+        Ok(())
+    }
+}
+
+fn main() {
+    let packet = Packet::builder().id(7).byte(1).byte(2).build().unwrap();
+    assert_eq!(packet.id, 7);
+    assert_eq!(packet.payload, [1, 2]);
+    assert_eq!(packet.label, None);
+    assert_eq!(packet.ttl, 0);
+
+    let err = packet.to_builder().ttl(100).build().unwrap_err();
+    assert_eq!(
+        err,
+        PacketBuilderError::InvalidField {
+            field: "ttl",
+            message: String::from("too large"),
+        }
+    );
+    let _: &dyn core::error::Error = &err;
+}
@@ -0,0 +1,106 @@
+// #[builder(build_fn(...))] customizes the method finishing the builder:
+//
+//   name = "finish"   name of the method instead of `build`
+//   error = "MyError" error type of the method, which needs to implement
+//                     From<NameBuilderError>
+//   private           do not make the method `pub`, e.g. to wrap it
+//   with = "path"     construct the value with `path`, which gets the field
+//                     values in declaration order once they are all set and
+//                     returns Result<T, E> where E converts into the error
+//   async             make the method async, the `with` function is awaited
+//
+// Field validation runs before the `with` function is called, struct
+// validation runs on the value it returns.
+
+use derive_builder::Builder;
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    Builder(ConfigBuilderError),
+}
+
+impl From<ConfigBuilderError> for ConfigError {
+    fn from(e: ConfigBuilderError) -> Self {
+        ConfigError::Builder(e)
+    }
+}
+
+#[derive(Builder, Debug)]
+#[builder(build_fn(name = "finish", error = "ConfigError", private))]
+pub struct Config {
+    name: String,
+}
+
+impl ConfigBuilder {
+    pub fn build(&mut self) -> Result<Config, ConfigError> {
+        self.finish()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConnectError {
+    Builder(ConnectionBuilderError),
+    Refused(u16),
+}
+
+impl From<ConnectionBuilderError> for ConnectError {
+    fn from(e: ConnectionBuilderError) -> Self {
+        ConnectError::Builder(e)
+    }
+}
+
+#[derive(Builder, Debug)]
+#[builder(build_fn(async, with = "io::connect", error = "ConnectError"))]
+pub struct Connection {
+    host: String,
+    port: u16,
+    #[builder(default = 3)]
+    retries: u32,
+}
+
+mod io {
+    use super::{ConnectError, Connection};
+
+    pub async fn connect(host: String, port: u16, retries: u32) -> Result<Connection, ConnectError> {
+        if port == 0 {
+            return Err(ConnectError::Refused(port));
+        }
+        Ok(Connection {
+            host,
+            port,
+            retries,
+        })
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+fn main() {
+    let err = Config::builder().build().unwrap_err();
+    assert_eq!(
+        err,
+        ConfigError::Builder(ConfigBuilderError::MissingFields(vec!["name"]))
+    );
+    let config = Config::builder().name("app".to_owned()).build().unwrap();
+    assert_eq!(config.name, "app");
+
+    let mut builder = Connection::builder();
+    builder.host("localhost".to_owned()).port(80);
+    let connection = block_on(builder.build()).unwrap();
+    assert_eq!(connection.host, "localhost");
+    assert_eq!(connection.retries, 3);
+
+    builder.port(0);
+    assert_eq!(block_on(builder.build()).unwrap_err(), ConnectError::Refused(0));
+}
@@ -0,0 +1,113 @@
+// A field whose type derives Builder itself can be built in place with
+// #[builder(sub_builder)].
+//
+// The outer builder stores the builder of the field: `field_mut()` returns it,
+// creating it empty on first use, and the setter takes a whole builder.
+// build() builds the sub-builders too, an unset one is built empty. Their
+// failures are reported as `Nested` errors with the path to the field that
+// failed, e.g. `server.port`.
+//
+// Builders of any pattern can be nested. A sub-builder is built synchronously
+// and reports the generated error, whatever its own `build_fn` options, except
+// that a `build_fn(with = "...")` that is async or has a custom error can not
+// be nested. An outer builder with the mutable or immutable pattern builds a
+// stored sub-builder from a reference, so an owned sub-builder then needs
+// #[builder(derive(Clone))].
+//
+// merge() merges sub-builders recursively.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Builder, Debug)]
+pub struct Database {
+    #[builder(sub_builder)]
+    server: Server,
+    name: String,
+}
+
+#[derive(Builder, Debug)]
+pub struct Config {
+    #[builder(sub_builder)]
+    database: Database,
+    #[builder(default)]
+    debug: bool,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned")]
+pub struct Limits {
+    max: u32,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned")]
+pub struct Quota {
+    #[builder(sub_builder)]
+    limits: Limits,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned", derive(Clone), build_fn(async))]
+pub struct Retry {
+    attempts: u8,
+}
+
+#[derive(Builder, Debug)]
+pub struct Client {
+    #[builder(sub_builder)]
+    retry: Retry,
+}
+
+fn main() {
+    let mut builder = Config::builder();
+    builder.database_mut().name("app".to_owned());
+    builder
+        .database_mut()
+        .server_mut()
+        .host("localhost".to_owned());
+
+    let err = builder.build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`database.server.port`: missing required field(s): `port`",
+    );
+    match err {
+        ConfigBuilderError::Nested { path, .. } => assert_eq!(path, "database.server.port"),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let mut overrides = Config::builder();
+    overrides.database_mut().server_mut().port(5432);
+    builder.merge(overrides);
+
+    let config = builder.build().unwrap();
+    assert_eq!(config.database.name, "app");
+    assert_eq!(config.database.server.host, "localhost");
+    assert_eq!(config.database.server.port, 5432);
+    assert!(!config.debug);
+
+    let mut server = Server::builder();
+    server.host("db".to_owned()).port(1);
+    let mut database = Database::builder();
+    database.server(server).name("other".to_owned());
+    let database = database.build().unwrap();
+    assert_eq!(database.server.host, "db");
+
+    let quota = Quota::builder()
+        .limits(Limits::builder().max(3))
+        .build()
+        .unwrap();
+    assert_eq!(quota.limits.max, 3);
+
+    let mut client = Client::builder();
+    client.retry(Retry::builder().attempts(2));
+    assert_eq!(client.build().unwrap().retry.attempts, 2);
+    let err = Client::builder().build().unwrap_err();
+    assert_eq!(err.to_string(), "`retry.attempts`: missing required field(s): `attempts`");
+}
@@ -0,0 +1,82 @@
+// Fields with `each` get the whole family of setters:
+//
+//   args(Vec<String>)                           replace the collection
+//   arg(String)                                 add one item
+//   extend_args(impl IntoIterator<Item = ...>)  add several items
+//   clear_args()                                empty the collection
+//
+// When the `each` name is the field name the item setter keeps that name and
+// the setter for the whole collection is named `set_{field}` instead, e.g.
+// set_env(Vec<String>) next to env(String). The long form
+// #[builder(each(name = "...", bulk = "..."))] names it explicitly.
+
+use derive_builder::Builder;
+use std::collections::BTreeMap;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env")]
+    env: Vec<String>,
+    #[builder(each = "var")]
+    vars: BTreeMap<String, String>,
+    #[builder(each(name = "path", bulk = "search_path"))]
+    paths: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Route {
+    path: String,
+    #[builder(each = "method")]
+    methods: Vec<String>,
+    #[builder(each = "header")]
+    header: Vec<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .args(vec!["a".to_owned(), "b".to_owned()])
+        .arg("c".to_owned())
+        .extend_args(["d".to_owned()])
+        .env("X=1".to_owned())
+        .clear_env()
+        .extend_env(vec!["Y=2".to_owned()])
+        .var(("k".to_owned(), "v".to_owned()))
+        .extend_vars([("l".to_owned(), "w".to_owned())])
+        .search_path(vec!["/bin".to_owned()])
+        .path("/usr/bin".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.args, ["a", "b", "c", "d"]);
+    assert_eq!(command.env, ["Y=2"]);
+    assert_eq!(command.vars.len(), 2);
+    assert_eq!(command.paths, ["/bin", "/usr/bin"]);
+
+    let command = Command::builder()
+        .set_env(vec!["A=1".to_owned(), "B=2".to_owned()])
+        .env("C=3".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.env, ["A=1", "B=2", "C=3"]);
+
+    let command = Command::builder()
+        .arg("a".to_owned())
+        .clear_args()
+        .build()
+        .unwrap();
+    assert!(command.args.is_empty());
+
+    let route = Route::builder()
+        .path("/".to_owned())
+        .method("GET".to_owned())
+        .clear_methods()
+        .methods(vec!["POST".to_owned()])
+        .extend_methods(["PUT".to_owned()])
+        .set_header(vec!["Accept".to_owned()])
+        .header("Host".to_owned())
+        .build();
+    assert_eq!(route.methods, ["POST", "PUT"]);
+    assert_eq!(route.header, ["Accept", "Host"]);
+}
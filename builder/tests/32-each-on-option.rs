@@ -0,0 +1,14 @@
+// #[builder(each = "...")] needs the collection itself as the field type. An
+// `Option` around it is reported instead of silently dropping the item
+// setter; an `each` collection already defaults to empty when never set.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Option<Vec<String>>,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+// Names given as strings, like `setter(name = "...")`, `each = "..."` or
+// `builder(name = "...")`, must be valid identifiers. Anything else is
+// reported on the string instead of making the derive panic.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(setter(name = "set-executable"))]
+    executable: String,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+// Every field read by `from_env()` is parsed with FromStr. A field whose type
+// does not implement it is reported on its type; such fields can be left out
+// with #[builder(env(skip))].
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(from_env)]
+pub struct Config {
+    name: String,
+    tags: Vec<String>,
+}
+
+fn main() {}
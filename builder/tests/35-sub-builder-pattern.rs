@@ -0,0 +1,20 @@
+// An outer builder with the mutable pattern builds its sub-builders from a
+// reference. An owned sub-builder is cloned for that, which is reported on the
+// field type when the sub-builder does not derive Clone.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Builder)]
+pub struct Config {
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+fn main() {}
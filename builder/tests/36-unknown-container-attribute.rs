@@ -0,0 +1,12 @@
+// Unknown keys in #[builder(...)] on the type are reported along with every
+// key the derive understands.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(bogus)]
+pub struct Command {
+    executable: String,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+// Setters can not take the name of a method the builder always has, like
+// `build`, `merge` or `missing_fields`. The field is reported along with how
+// to rename its setter.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Branch {
+    name: String,
+    merge: u8,
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+// A sub-builder is built synchronously with the generated error type, which a
+// `build_fn(with = "...")` that is async can not provide. Such a builder can
+// not be nested, which is reported on the field type.
+
+use derive_builder::Builder;
+
+async fn connect(host: String) -> Result<Server, ServerBuilderError> {
+    Ok(Server { host })
+}
+
+#[derive(Builder)]
+#[builder(build_fn(async, with = "connect"))]
+pub struct Server {
+    host: String,
+}
+
+#[derive(Builder)]
+pub struct Config {
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+fn main() {}
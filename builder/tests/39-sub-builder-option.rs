@@ -0,0 +1,24 @@
+// #[builder(sub_builder)] builds the field with the builder of its type, which
+// `Option` and std collections do not have. They are rejected like any type
+// that does not name a struct deriving Builder.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Server {
+    host: String,
+}
+
+#[derive(Builder)]
+pub struct Config {
+    #[builder(sub_builder)]
+    server: Option<Server>,
+}
+
+#[derive(Builder)]
+pub struct Cluster {
+    #[builder(sub_builder)]
+    replicas: Vec<Server>,
+}
+
+fn main() {}
@@ -10,4 +10,36 @@ fn tests() {
     t.pass("tests/07-repeated-field.rs");
     t.compile_fail("tests/08-unrecognized-attribute.rs");
     t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-missing-fields.rs");
+    t.pass("tests/11-typestate.rs");
+    t.compile_fail("tests/12-typestate-missing-field.rs");
+    t.pass("tests/13-generics.rs");
+    t.pass("tests/14-default-value.rs");
+    t.pass("tests/15-nested-types.rs");
+    t.pass("tests/16-each-collections.rs");
+    t.pass("tests/17-setter-options.rs");
+    t.pass("tests/18-builder-pattern.rs");
+    t.pass("tests/19-validate.rs");
+    t.pass("tests/20-tuple-and-enum.rs");
+    if cfg!(feature = "serde") {
+        t.pass("tests/21-deserialize.rs");
+    }
+    t.pass("tests/22-merge.rs");
+    t.compile_fail("tests/23-merge-without-each.rs");
+    t.pass("tests/24-from-env.rs");
+    t.pass("tests/25-getters.rs");
+    t.pass("tests/26-to-builder.rs");
+    t.pass("tests/27-builder-attributes.rs");
+    t.pass("tests/28-no-std.rs");
+    t.pass("tests/29-build-fn.rs");
+    t.pass("tests/30-sub-builder.rs");
+    t.pass("tests/31-each-and-bulk-setters.rs");
+    t.compile_fail("tests/32-each-on-option.rs");
+    t.compile_fail("tests/33-invalid-name.rs");
+    t.compile_fail("tests/34-from-env-without-from-str.rs");
+    t.compile_fail("tests/35-sub-builder-pattern.rs");
+    t.compile_fail("tests/36-unknown-container-attribute.rs");
+    t.compile_fail("tests/37-setter-name-clash.rs");
+    t.compile_fail("tests/38-sub-builder-async-with.rs");
+    t.compile_fail("tests/39-sub-builder-option.rs");
 }
@@ -1,15 +1,16 @@
 use proc_macro::TokenStream;
-use proc_macro2::{Ident, Span};
-use quote::quote;
+use proc_macro2::Ident;
+use quote::{format_ident, quote, ToTokens};
 use std::clone::Clone;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use syn::meta::ParseNestedMeta;
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::token::{Colon, Where};
 use syn::{
-    parse_macro_input, parse_quote, Data, DeriveInput, Expr, ExprLit, Fields, GenericArgument,
-    GenericParam, Generics, Lit, Meta, MetaNameValue, Path, PathArguments, PathSegment,
-    PredicateType, Token, TraitBound, TraitBoundModifier, Type, TypeParamBound, TypePath,
-    WhereClause, WherePredicate,
+    parse_macro_input, parse_quote, Data, DeriveInput, Expr, ExprLit, Field, Fields,
+    GenericArgument, GenericParam, Generics, Lit, LitStr, Member, Meta, MetaNameValue, Path,
+    PathArguments, PredicateType, Token, Type, TypePath, WhereClause, WherePredicate,
 };
 
 macro_rules! compile_error {
@@ -43,36 +44,35 @@ pub fn derive(input: TokenStream) -> TokenStream {
     // } else {
     //     panic!("{:#?}", ast);
     // }
-    let mut field_vec: Vec<proc_macro2::TokenStream> = vec![];
     // For `PhantomData` type, if the generic type (such as `T`) only exists in `PhantomData`, do
     // not generate trait bound for `T`.
-    // Here uses a `HashMap` to record whether a generic type only exists in `PhantomData`.
-    // If true, Ident only exists in `PhantomData`.
-    // If false, Ident not exists in `PhantomData` or both in/out `PhantomData`.
-    let mut phantom_generic_map: HashMap<Ident, (bool, Option<Type>)> = HashMap::new();
-
-    let Data::Struct(data_struct) = &ast.data else {
-        return compile_error!(ident.span(), "invalid struct type");
-    };
-    let Fields::Named(named_fields) = &data_struct.fields else {
-        return TokenStream::new();
-    };
+    // Here uses a `HashMap` to record all generic types, along with the types to bound for
+    // each of them: the generic type itself, such as `T`, or its associated types when fields
+    // only use them, such as `T::Value`.
+    let mut phantom_generic_map: HashMap<Ident, Vec<Type>> = HashMap::new();
+    // Generics used by fields formatted with their `Debug` impl, along with the bounds given on
+    // fields.
+    let mut field_bounds = FieldBounds::default();
 
     // 08-escape-hatch
     // Find and record `#[debug(bound = "T::Value: Debug")]`
-    let mut outer_bound: Option<String> = None;
+    let mut outer_bound: Option<Vec<WherePredicate>> = None;
     for attr in &ast.attrs {
-        if let Meta::List(meta_list) = &attr.meta {
-            if !meta_list.path.segments.is_empty()
-                && meta_list.path.segments.first().unwrap().ident == "debug"
-            {
-                match trait_bound_token(&meta_list.tokens) {
-                    Some(bound) => {
-                        outer_bound = Some(bound);
-                    }
-                    None => continue,
-                }
+        if !matches!(&attr.meta, Meta::List(_)) || !attr.path().is_ident("debug") {
+            continue;
+        }
+        let parsed = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("bound") {
+                outer_bound
+                    .get_or_insert_with(Vec::new)
+                    .extend(parse_bound(&meta)?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `debug(bound = \"...\")`"))
             }
+        });
+        if let Err(e) = parsed {
+            return e.to_compile_error().into();
         }
     }
 
@@ -97,7 +97,7 @@ pub fn derive(input: TokenStream) -> TokenStream {
     // Record all generic type names.
     for param in &ast.generics.params {
         if let GenericParam::Type(gt) = param {
-            phantom_generic_map.insert(gt.ident.clone(), (false, None));
+            phantom_generic_map.insert(gt.ident.clone(), Vec::new());
         }
     }
 
@@ -114,168 +114,404 @@ pub fn derive(input: TokenStream) -> TokenStream {
         },
     );
 
-    for named_field in &named_fields.named {
-        let mut debug_attr_format: Option<String> = None;
-        for attr in &named_field.attrs {
-            let p = attr.meta.path().segments.first();
-            if p.is_none() || p.unwrap().ident != "debug" {
-                continue;
+    let debug_expr = match &ast.data {
+        Data::Struct(data_struct) => match debug_fields(
+            ident,
+            &data_struct.fields,
+            |index, field| {
+                let member = field
+                    .ident
+                    .clone()
+                    .map_or_else(|| Member::Unnamed(index.into()), Member::Named);
+                quote!(&self.#member)
+            },
+            &mut phantom_generic_map,
+            &mut field_bounds,
+        ) {
+            Ok(v) => v,
+            Err(e) => return e.to_compile_error().into(),
+        },
+        // Every variant gets a match arm binding its fields as `__self_0`, `__self_1`, ...,
+        // which are references thanks to match ergonomics.
+        Data::Enum(data_enum) => {
+            let mut arm_vec: Vec<proc_macro2::TokenStream> = vec![];
+            for variant in &data_enum.variants {
+                let variant_ident = &variant.ident;
+                let bindings: Vec<Ident> = (0..variant.fields.len())
+                    .map(|index| format_ident!("__self_{}", index))
+                    .collect();
+                let pattern = match &variant.fields {
+                    Fields::Named(named_fields) => {
+                        let names = named_fields.named.iter().map(|v| &v.ident);
+                        quote!(Self::#variant_ident { #(#names: #bindings),* })
+                    }
+                    Fields::Unnamed(_) => quote!(Self::#variant_ident(#(#bindings),*)),
+                    Fields::Unit => quote!(Self::#variant_ident),
+                };
+                let expr = match debug_fields(
+                    variant_ident,
+                    &variant.fields,
+                    |index, _| {
+                        let binding = &bindings[index];
+                        quote!(#binding)
+                    },
+                    &mut phantom_generic_map,
+                    &mut field_bounds,
+                ) {
+                    Ok(v) => v,
+                    Err(e) => return e.to_compile_error().into(),
+                };
+                arm_vec.push(quote!(#pattern => #expr));
             }
-
-            // The following code may be a prettier version of nested `if let` expressions like this:
-            //
-            // ```
-            // if let syn::Meta::NameValue(meta) = &attr.meta {
-            //     if let syn::Expr::Lit(lit) = &meta.value {
-            //         if let syn::Lit::Str(s) = &lit.lit {
-            //             debug_attr_format =
-            //                 Some(s.token().to_string().trim_matches('"').to_string());
-            //             // eprintln!("lit: {}", s.token());
-            //             // eprintln!("type:{:?}", debug_attr_format);
-            //         }
-            //     }
-            // }
-            // ```
-            if let Meta::NameValue(MetaNameValue {
-                value:
-                    Expr::Lit(ExprLit {
-                        lit: Lit::Str(ref s),
-                        ..
-                    }),
-                ..
-            }) = &attr.meta
-            {
-                debug_attr_format = Some(s.token().to_string().trim_matches('"').to_string());
-                // eprintln!("lit: {}", s.token());
-                // eprintln!("type:{:?}", debug_attr_format);
+            // An empty enum has no value to format.
+            if arm_vec.is_empty() {
+                quote!(match *self {})
+            } else {
+                quote!(match self {
+                    #(#arm_vec,)*
+                })
             }
         }
+        Data::Union(_) => {
+            return compile_error!(
+                ident.span(),
+                "invalid derive type: unions are not supported"
+            );
+        }
+    };
 
-        let i = &named_field.ident.as_ref().unwrap();
-        let s = ident_token_str!(i);
-
-        if let Type::Path(TypePath {
-            path: Path { segments, .. },
-            ..
-        }) = &named_field.ty
-        {
-            for segment in segments {
-                // For 04-type-parameter, T.
-                // struct S<T> {
-                //   foo: T,
-                // }
-                if phantom_generic_map.contains_key(&segment.ident) {
-                    break;
-                }
-                // For 05-phantom-data, T.
-                // struct S<T> {
-                //   foo: Bar<T>,
-                // }
-                if let PathArguments::AngleBracketed(ab) = &segment.arguments {
-                    // For 05-phantom-data, PhantomData<T>.
-                    // struct S<T> {
-                    //   foo: PhantomData<T>,
-                    // }
-                    if ab.args.is_empty() {
-                        continue;
-                    }
+    // Avoid add trait bound to generics only used in `PhantomData`, or only used by fields
+    // which are skipped, redacted, formatted `with` a function or bringing their own bounds.
+    // Bounds on the type replace every inferred bound, but keep the where clause of the type
+    // and the bounds given on fields.
+    for (generic_ident, bounded_vec) in &phantom_generic_map {
+        if outer_bound.is_some() || !field_bounds.inferred_generics.contains(generic_ident) {
+            continue;
+        }
+        for bounded_ty in bounded_vec {
+            where_clause_ex
+                .predicates
+                .push(WherePredicate::Type(PredicateType {
+                    lifetimes: None,
+                    bounded_ty: bounded_ty.clone(),
+                    colon_token: Colon::default(),
+                    bounds: parse_quote!(std::fmt::Debug),
+                }));
+        }
+    }
 
-                    for arg in &ab.args {
-                        if let GenericArgument::Type(Type::Path(TypePath { path, .. })) = arg {
-                            let ss = &path.segments;
-                            if !phantom_generic_map.contains_key(&ss.first().unwrap().clone().ident)
-                            {
-                                continue;
-                            }
-                            if segment.ident == "PhantomData" {
-                                phantom_generic_map
-                                    .insert(ss.first().unwrap().clone().ident, (true, None));
-                            } else {
-                                // For 07-associated-type, T::Value, where T is a trait.
-                                // struct Foo<T: Trait> {
-                                //   values: Vec<T::Value>,
-                                // }
-                                // Here store full path to generate trait bound looks like T::Value : Debug.
-                                phantom_generic_map.insert(
-                                    ss.first().unwrap().clone().ident,
-                                    (
-                                        false,
-                                        Some(Type::Path(TypePath {
-                                            qself: None,
-                                            path: path.clone(),
-                                        })),
-                                    ),
-                                );
-                            }
-                        }
-                    }
-                }
+    let body = quote!(
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>)  -> std::fmt::Result {
+                #debug_expr
             }
+    );
+
+    where_clause_ex
+        .predicates
+        .extend(outer_bound.into_iter().flatten());
+    where_clause_ex.predicates.extend(field_bounds.predicates);
+
+    quote!(
+        impl #impl_generics std::fmt::Debug for #ident #ty_generics #where_clause_ex {
+            #body
         }
+    )
+    .into()
+}
 
-        // panic!("where: {:#?}", where_clause_ex.predicates);
+/// Build the expression formatting `fields` of a struct or an enum variant named `name`:
+/// `debug_struct` for named fields, `debug_tuple` for unnamed fields and just the name for unit
+/// structs and variants.
+///
+/// `access` gives a reference to the field value, generic types used by the fields formatted
+/// with their `Debug` impl are recorded in `field_bounds`.
+fn debug_fields(
+    name: &Ident,
+    fields: &Fields,
+    access: impl Fn(usize, &Field) -> proc_macro2::TokenStream,
+    phantom_generic_map: &mut HashMap<Ident, Vec<Type>>,
+    field_bounds: &mut FieldBounds,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut field_vec: Vec<proc_macro2::TokenStream> = vec![];
 
-        let field_print = debug_attr_format.map_or_else(
-            || {
-                quote!(
-                    .field(#s, &self.#i)
-                )
-            },
-            |v| {
-                // Here use ident_token_str! macro rules to make an literal ident.
-                let format_token = ident_token_str!(v);
-                quote!(
-                    .field(#s, &format_args!(#format_token, &self.#i))
-                )
-            },
-        );
+    for (index, field) in fields.iter().enumerate() {
+        let opts = parse_field_opts(field)?;
+        if opts.skip {
+            continue;
+        }
+        let value = access(index, field);
+        let value = if let Some(redact) = &opts.redact {
+            // The value is never read, so its type does not need a bound.
+            quote!(&format_args!("{}", #redact))
+        } else if let Some(with) = &opts.with {
+            // The value is formatted by `with`, so its type does not need a bound either.
+            quote!(&{
+                struct DebugWith<'a, T: ?Sized>(
+                    &'a T,
+                    fn(&T, &mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+                );
+                impl<T: ?Sized> std::fmt::Debug for DebugWith<'_, T> {
+                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        (self.1)(self.0, f)
+                    }
+                }
+                DebugWith(#value, #with)
+            })
+        } else {
+            if let Some(bound) = opts.bound {
+                // The given bounds replace the ones inferred from this field.
+                field_bounds.predicates.extend(bound);
+            } else {
+                let mut mentioned = HashSet::new();
+                mentioned_generics(
+                    field.ty.to_token_stream(),
+                    phantom_generic_map,
+                    &mut mentioned,
+                );
+                let recorded = record_generics(&field.ty, phantom_generic_map);
+                // Generic types used by the field in other ways, like `T` or `&T`, are bounded
+                // themselves.
+                for generic_ident in mentioned.difference(&recorded) {
+                    add_bounded_type(
+                        phantom_generic_map,
+                        generic_ident,
+                        parse_quote!(#generic_ident),
+                    );
+                }
+                field_bounds.inferred_generics.extend(mentioned);
+            }
+            opts.format.map_or_else(
+                || value.clone(),
+                |v| {
+                    // Here use ident_token_str! macro rules to make an literal ident.
+                    let format_token = ident_token_str!(v);
+                    quote!(&format_args!(#format_token, #value))
+                },
+            )
+        };
+        let field_print = match (&field.ident, &opts.rename) {
+            (_, Some(rename)) => quote!(.field(#rename, #value)),
+            (Some(i), None) => {
+                let s = ident_token_str!(i);
+                quote!(.field(#s, #value))
+            }
+            (None, None) => quote!(.field(#value)),
+        };
 
         field_vec.push(field_print);
     }
 
-    // Avoid add trait bound to generics only used in `PhantomData`.
-    for (generic_ident, (only_in_phantom, ident_vec)) in &phantom_generic_map {
-        if *only_in_phantom {
+    let s = ident_token_str!(name);
+    Ok(match fields {
+        Fields::Named(_) => quote!(
+            f.debug_struct(#s)
+            #(#field_vec)*
+            .finish()
+        ),
+        Fields::Unnamed(_) => quote!(
+            f.debug_tuple(#s)
+            #(#field_vec)*
+            .finish()
+        ),
+        Fields::Unit => quote!(f.write_str(#s)),
+    })
+}
+
+/// Options set on a field through `#[debug = "..."]` and `#[debug(...)]`.
+#[derive(Default)]
+struct FieldOpts {
+    /// `#[debug = "..."]`, format string used for the value.
+    format: Option<String>,
+    /// `#[debug(skip)]`, leave the field out.
+    skip: bool,
+    /// `#[debug(rename = "...")]`, name shown instead of the field name.
+    rename: Option<LitStr>,
+    /// `#[debug(redact)]` or `#[debug(redact = "...")]`, text shown instead of the value.
+    redact: Option<LitStr>,
+    /// `#[debug(with = "...")]`, function formatting the value.
+    with: Option<Path>,
+    /// `#[debug(bound = "...")]`, bounds used instead of the ones inferred from the field type.
+    bound: Option<Vec<WherePredicate>>,
+}
+
+/// Bounds collected from the fields.
+#[derive(Default)]
+struct FieldBounds {
+    /// Generic types used outside of `PhantomData` by fields with inferred bounds.
+    inferred_generics: HashSet<Ident>,
+    /// Bounds given by fields with `#[debug(bound = "...")]`.
+    predicates: Vec<WherePredicate>,
+}
+
+fn parse_field_opts(field: &Field) -> syn::Result<FieldOpts> {
+    let mut opts = FieldOpts::default();
+    for attr in &field.attrs {
+        let p = attr.meta.path().segments.first();
+        if p.is_none() || p.unwrap().ident != "debug" {
             continue;
         }
-        where_clause_ex
-            .predicates
-            .push(WherePredicate::Type(PredicateType {
-                lifetimes: None,
-                bounded_ty: ident_vec
-                    .as_ref()
-                    .map_or_else(|| parse_quote!(#generic_ident), Clone::clone),
-                colon_token: Colon::default(),
-                bounds: parse_quote!(std::fmt::Debug),
-            }));
-    }
 
-    let s = ident_token_str!(ident);
+        // The following code may be a prettier version of nested `if let` expressions like this:
+        //
+        // ```
+        // if let syn::Meta::NameValue(meta) = &attr.meta {
+        //     if let syn::Expr::Lit(lit) = &meta.value {
+        //         if let syn::Lit::Str(s) = &lit.lit {
+        //             debug_attr_format =
+        //                 Some(s.token().to_string().trim_matches('"').to_string());
+        //             // eprintln!("lit: {}", s.token());
+        //             // eprintln!("type:{:?}", debug_attr_format);
+        //         }
+        //     }
+        // }
+        // ```
+        if let Meta::NameValue(MetaNameValue {
+            value:
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(ref s),
+                    ..
+                }),
+            ..
+        }) = &attr.meta
+        {
+            opts.format = Some(s.token().to_string().trim_matches('"').to_string());
+            // eprintln!("lit: {}", s.token());
+            // eprintln!("type:{:?}", debug_attr_format);
+            continue;
+        }
 
-    let body = quote!(
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>)  -> std::fmt::Result {
-                f.debug_struct(#s)
-                #(#field_vec)*
-                .finish()
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                opts.skip = true;
+            } else if meta.path.is_ident("rename") {
+                if field.ident.is_none() {
+                    return Err(meta.error("`rename` is only for named fields"));
+                }
+                opts.rename = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("redact") {
+                opts.redact = Some(if meta.input.peek(Token![=]) {
+                    meta.value()?.parse()?
+                } else {
+                    LitStr::new("[REDACTED]", meta.path.span())
+                });
+            } else if meta.path.is_ident("bound") {
+                opts.bound
+                    .get_or_insert_with(Vec::new)
+                    .extend(parse_bound(&meta)?);
+            } else if meta.path.is_ident("with") {
+                // `with = "path::to::fn"` or `with = path::to::fn`.
+                let value = meta.value()?;
+                opts.with = Some(if value.peek(LitStr) {
+                    value.parse::<LitStr>()?.parse()?
+                } else {
+                    value.parse()?
+                });
+            } else {
+                return Err(meta.error(
+                    "expected `debug = \"...\"`, `debug(skip)`, `debug(rename = \"...\")`, `debug(redact)`, `debug(with = \"...\")` or `debug(bound = \"...\")`",
+                ));
             }
-    );
+            Ok(())
+        })?;
+    }
+    if usize::from(opts.skip)
+        + usize::from(opts.redact.is_some())
+        + usize::from(opts.format.is_some())
+        + usize::from(opts.with.is_some())
+        > 1
+    {
+        return Err(syn::Error::new_spanned(
+            field,
+            "only one of `debug = \"...\"`, `debug(skip)`, `debug(redact)` and `debug(with = \"...\")` can be used",
+        ));
+    }
+    if opts.bound.is_some() && (opts.skip || opts.redact.is_some() || opts.with.is_some()) {
+        return Err(syn::Error::new_spanned(
+            field,
+            "`debug(bound = \"...\")` has no effect with `debug(skip)`, `debug(redact)` or `debug(with = \"...\")`, which do not format the value with its Debug impl",
+        ));
+    }
+    Ok(opts)
+}
 
-    let expanded: TokenStream = outer_bound.map_or_else(|| quote!(
-            impl #impl_generics std::fmt::Debug for #ident #ty_generics #where_clause_ex {
-                #body
+/// Record in `phantom_generic_map` the types to bound for the generic types used as type
+/// arguments by a field of type `ty`, and return these generic types.
+fn record_generics(
+    ty: &Type,
+    phantom_generic_map: &mut HashMap<Ident, Vec<Type>>,
+) -> HashSet<Ident> {
+    let mut recorded = HashSet::new();
+    let Type::Path(TypePath {
+        path: Path { segments, .. },
+        ..
+    }) = ty
+    else {
+        return recorded;
+    };
+    for segment in segments {
+        // For 04-type-parameter, T.
+        // struct S<T> {
+        //   foo: T,
+        // }
+        if phantom_generic_map.contains_key(&segment.ident) {
+            break;
+        }
+        // For 05-phantom-data, T.
+        // struct S<T> {
+        //   foo: Bar<T>,
+        // }
+        if let PathArguments::AngleBracketed(ab) = &segment.arguments {
+            // For 05-phantom-data, PhantomData<T>.
+            // struct S<T> {
+            //   foo: PhantomData<T>,
+            // }
+            if ab.args.is_empty() {
+                continue;
             }
-        )
-        .into(), |bound| {
-            let outer_bound_where_clause = str_to_where_clause(bound.as_str(), ident.span());
-            quote!(
-                impl #impl_generics std::fmt::Debug for #ident #ty_generics #outer_bound_where_clause {
-                    #body
+
+            for arg in &ab.args {
+                if let GenericArgument::Type(Type::Path(TypePath { path, .. })) = arg {
+                    let ss = &path.segments;
+                    if !phantom_generic_map.contains_key(&ss.first().unwrap().clone().ident) {
+                        continue;
+                    }
+                    // `PhantomData<T>` does not need `T: Debug`.
+                    if segment.ident != "PhantomData" {
+                        // For 07-associated-type, T::Value, where T is a trait.
+                        // struct Foo<T: Trait> {
+                        //   values: Vec<T::Value>,
+                        // }
+                        // Here store full path to generate trait bound looks like T::Value : Debug.
+                        let generic_ident = &ss.first().unwrap().ident;
+                        add_bounded_type(
+                            phantom_generic_map,
+                            generic_ident,
+                            Type::Path(TypePath {
+                                qself: None,
+                                path: path.clone(),
+                            }),
+                        );
+                        recorded.insert(generic_ident.clone());
+                    }
                 }
-            )
-                .into()
-        });
+            }
+        }
+    }
+    recorded
+}
 
-    expanded
+/// Add `ty` to the types to bound for `generic_ident`, once.
+fn add_bounded_type(
+    phantom_generic_map: &mut HashMap<Ident, Vec<Type>>,
+    generic_ident: &Ident,
+    ty: Type,
+) {
+    let bounded_vec = phantom_generic_map
+        .entry(generic_ident.clone())
+        .or_default();
+    if !bounded_vec.contains(&ty) {
+        bounded_vec.push(ty);
+    }
 }
 
 // Comments in 04-type-parameter said that this macro should add trait bound.
@@ -290,91 +526,54 @@ fn add_trait_bounds(mut generics: Generics) -> Generics {
     generics
 }
 
-// FIXME: Now only check type, sort not checked.
-fn trait_bound_token(token: &proc_macro2::TokenStream) -> Option<String> {
-    if token.is_empty() {
-        return None;
-    }
-    for tt in token.clone() {
+/// Parse `bound = "..."` as comma separated where predicates, e.g.
+/// `"T::Value: Debug, for<'a> &'a T: IntoIterator"`.
+fn parse_bound(meta: &ParseNestedMeta) -> syn::Result<Punctuated<WherePredicate, Token![,]>> {
+    let bound: LitStr = meta.value()?.parse()?;
+    bound.parse_with(Punctuated::parse_terminated)
+}
+
+/// Add the generic types of `phantom_generic_map` appearing in `tokens` to `found`, leaving out
+/// the ones only appearing as arguments of `PhantomData<...>`.
+fn mentioned_generics(
+    tokens: proc_macro2::TokenStream,
+    phantom_generic_map: &HashMap<Ident, Vec<Type>>,
+    found: &mut HashSet<Ident>,
+) {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(tt) = tokens.next() {
         match tt {
-            proc_macro2::TokenTree::Ident(i) => {
-                if i != "bound" {
-                    return None;
+            proc_macro2::TokenTree::Ident(i) if i == "PhantomData" => {
+                if !matches!(tokens.peek(), Some(proc_macro2::TokenTree::Punct(p)) if p.as_char() == '<')
+                {
+                    continue;
                 }
-            }
-            proc_macro2::TokenTree::Punct(p) => {
-                if p.as_char() != '=' {
-                    return None;
+                // Skip up to the matching `>`, the `>` of `->` does not close anything.
+                let mut depth = 0usize;
+                let mut arrow = false;
+                for tt in tokens.by_ref() {
+                    if let proc_macro2::TokenTree::Punct(p) = &tt {
+                        match p.as_char() {
+                            '<' => depth += 1,
+                            '>' if !arrow => depth -= 1,
+                            _ => {}
+                        }
+                        arrow = p.as_char() == '-' && p.spacing() == proc_macro2::Spacing::Joint;
+                    } else {
+                        arrow = false;
+                    }
+                    if depth == 0 {
+                        break;
+                    }
                 }
             }
-            proc_macro2::TokenTree::Literal(l) => {
-                return Some(String::from(l.to_string().trim_matches('"')));
+            proc_macro2::TokenTree::Ident(i) if phantom_generic_map.contains_key(&i) => {
+                found.insert(i);
             }
-            proc_macro2::TokenTree::Group(_) => continue,
+            proc_macro2::TokenTree::Group(g) => {
+                mentioned_generics(g.stream(), phantom_generic_map, found);
+            }
+            _ => {}
         }
     }
-    None
-}
-
-fn str_to_where_clause(str: &str, span: Span) -> Option<WhereClause> {
-    let Some(pos) = str.rfind(':') else {
-        return None;
-    };
-
-    let s1 = str[..pos].to_string();
-    let s2 = str[pos + 1..].to_string();
-
-    let ident = s1.trim();
-    let bound = s2.trim();
-
-    let mut wc = WhereClause {
-        where_token: Where::default(),
-        predicates: Punctuated::default(),
-    };
-
-    // let mut path_segment_punctuated: Punctuated<PathSegment, Token![::]> = Punctuated::new();
-    //
-    // for part in ident.split("::") {
-    //     path_segment_punctuated.push(PathSegment {
-    //         ident: Ident::new(part, span),
-    //         arguments: Default::default(),
-    //     });
-    // }
-
-    let mut bounds_punctuated: Punctuated<TypeParamBound, Token![+]> = Punctuated::new();
-    bounds_punctuated.push(TypeParamBound::Trait(TraitBound {
-        paren_token: None,
-        modifier: TraitBoundModifier::None,
-        lifetimes: None,
-        path: Path {
-            leading_colon: None,
-            segments: build_path_segment_punctuated(bound, span),
-        },
-    }));
-
-    wc.predicates.push(WherePredicate::Type(PredicateType {
-        lifetimes: None,
-        bounded_ty: Type::Path(TypePath {
-            qself: None,
-            path: Path {
-                leading_colon: None,
-                segments: build_path_segment_punctuated(ident, span),
-            },
-        }),
-        colon_token: Colon::default(),
-        bounds: bounds_punctuated,
-    }));
-
-    Some(wc)
-}
-
-fn build_path_segment_punctuated(s: &str, span: Span) -> Punctuated<PathSegment, Token![::]> {
-    let mut path_segment_punctuated: Punctuated<PathSegment, Token![::]> = Punctuated::new();
-    for part in s.split("::") {
-        path_segment_punctuated.push(PathSegment {
-            ident: Ident::new(part, span),
-            arguments: PathArguments::default(),
-        });
-    }
-    path_segment_punctuated
 }
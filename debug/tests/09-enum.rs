@@ -0,0 +1,81 @@
+// Enums get a Debug impl too, formatting the active variant the way the
+// standard derive does: unit variants as their name, tuple variants with
+// debug_tuple and struct variants with debug_struct.
+//
+// The #[debug = "..."] attribute works on variant fields, and trait bounds
+// are inferred from the variant fields like they are for struct fields, so
+// type parameters only used in PhantomData do not need to implement Debug.
+// A type parameter used directly by one variant still needs Debug, even if
+// another variant only uses it in PhantomData. Variants mixing a type
+// parameter and its associated types get bounds for both.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+pub enum Shape<T, U> {
+    Empty,
+    Circle(T),
+    Rect {
+        #[debug = "0b{:08b}"]
+        width: u8,
+        height: T,
+    },
+    Marker(PhantomData<U>),
+}
+
+#[derive(CustomDebug)]
+pub enum Either<T> {
+    Value(T),
+    Marker(PhantomData<T>),
+}
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+pub enum Node<T: Trait> {
+    Leaf(T),
+    Values(Vec<T::Value>),
+}
+
+#[derive(CustomDebug)]
+pub enum Never {}
+
+struct NotDebug;
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    assert_debug::<Shape<u8, NotDebug>>();
+    assert_debug::<Either<u8>>();
+    assert_debug::<Never>();
+
+    impl Trait for u8 {
+        type Value = String;
+    }
+    assert_debug::<Node<u8>>();
+
+    let empty: Shape<u8, NotDebug> = Shape::Empty;
+    assert_eq!(format!("{:?}", empty), "Empty");
+
+    let circle: Shape<u8, NotDebug> = Shape::Circle(3);
+    assert_eq!(format!("{:?}", circle), "Circle(3)");
+
+    let rect: Shape<u8, NotDebug> = Shape::Rect {
+        width: 5,
+        height: 7,
+    };
+    assert_eq!(format!("{:?}", rect), "Rect { width: 0b00000101, height: 7 }");
+
+    let marker: Shape<u8, NotDebug> = Shape::Marker(PhantomData);
+    assert!(format!("{:?}", marker).starts_with("Marker(PhantomData<"));
+
+    let value: Either<u8> = Either::Value(9);
+    assert_eq!(format!("{:?}", value), "Value(9)");
+
+    let values: Node<u8> = Node::Values(vec!["a".to_owned()]);
+    assert_eq!(format!("{:?}", values), r#"Values(["a"])"#);
+}
@@ -0,0 +1,21 @@
+// Tuple structs are formatted with debug_tuple like the standard derive,
+// #[debug = "..."] works on their positional fields, and unit structs are
+// formatted as their name.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Meters(f64);
+
+#[derive(CustomDebug)]
+pub struct Rgb(#[debug = "{:#04x}"] u8, #[debug = "{:#04x}"] u8, u8);
+
+#[derive(CustomDebug)]
+pub struct Unit;
+
+fn main() {
+    assert_eq!(format!("{:?}", Meters(1.5)), "Meters(1.5)");
+    assert_eq!(format!("{:?}", Rgb(255, 16, 0)), "Rgb(0xff, 0x10, 0)");
+    assert_eq!(format!("{:?}", Unit), "Unit");
+    assert_eq!(format!("{:#?}", Meters(2.0)), "Meters(\n    2.0,\n)");
+}
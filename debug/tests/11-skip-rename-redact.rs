@@ -0,0 +1,78 @@
+// Fields can be left out or masked in the Debug output:
+//
+//   #[debug(skip)]             leave the field out
+//   #[debug(rename = "alias")] show the field under another name
+//   #[debug(redact)]           show [REDACTED] instead of the value
+//   #[debug(redact = "***")]   show the given text instead of the value
+//
+// Skipped and redacted fields are never formatted, so their types do not need
+// to implement Debug, and neither do type parameters only used by them.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+struct Buffer(#[allow(dead_code)] Vec<u8>);
+
+#[derive(CustomDebug)]
+struct Login {
+    #[debug(rename = "user")]
+    username: String,
+    #[debug(redact)]
+    password: String,
+    #[debug(redact = "***")]
+    token: String,
+    #[debug(skip)]
+    buffer: Buffer,
+}
+
+#[derive(CustomDebug)]
+enum Credential {
+    Password(#[debug(redact)] String),
+    Key {
+        id: u32,
+        #[debug(skip)]
+        bytes: Buffer,
+    },
+}
+
+#[derive(CustomDebug)]
+struct Cache<K, V> {
+    name: String,
+    #[debug(skip)]
+    keys: Vec<K>,
+    #[debug(redact)]
+    value: V,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    assert_debug::<Cache<Buffer, Buffer>>();
+
+    let login = Login {
+        username: "ferris".to_owned(),
+        password: "hunter2".to_owned(),
+        token: "abc".to_owned(),
+        buffer: Buffer(vec![0; 1024]),
+    };
+    assert_eq!(
+        format!("{:?}", login),
+        r#"Login { user: "ferris", password: [REDACTED], token: *** }"#,
+    );
+
+    let password = Credential::Password("hunter2".to_owned());
+    assert_eq!(format!("{:?}", password), "Password([REDACTED])");
+
+    let key = Credential::Key {
+        id: 7,
+        bytes: Buffer(vec![]),
+    };
+    assert_eq!(format!("{:?}", key), "Key { id: 7 }");
+
+    let cache = Cache {
+        name: "lru".to_owned(),
+        keys: vec![Buffer(vec![])],
+        value: Buffer(vec![1]),
+    };
+    assert_eq!(format!("{:?}", cache), r#"Cache { name: "lru", value: [REDACTED] }"#);
+}
@@ -0,0 +1,13 @@
+// Unknown #[debug(...)] field attributes are reported instead of being
+// ignored, so a typo like `redcat` does not leak the value.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Login {
+    username: String,
+    #[debug(redcat)]
+    password: String,
+}
+
+fn main() {}
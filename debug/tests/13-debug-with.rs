@@ -0,0 +1,71 @@
+// #[debug(with = "path")] formats a field with a function instead of its
+// Debug impl, e.g. to show bytes as hex. The function has the signature
+// `fn(&T, &mut fmt::Formatter) -> fmt::Result` where T is the field type.
+//
+// The field type does not need to implement Debug, and neither do type
+// parameters only used by fields formatted with a function.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+
+mod hex {
+    use std::fmt::{self, Debug};
+
+    pub fn bytes(bytes: &Vec<u8>, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in bytes {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+fn seconds(value: &Duration, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}s", value.0)
+}
+
+struct Duration(u64);
+
+#[derive(CustomDebug)]
+struct Packet {
+    #[debug(with = "hex::bytes")]
+    payload: Vec<u8>,
+    #[debug(with = seconds)]
+    ttl: Duration,
+}
+
+#[derive(CustomDebug)]
+enum Message {
+    Raw(#[debug(with = "hex::bytes")] Vec<u8>),
+}
+
+fn opaque<T>(_: &T, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("..")
+}
+
+#[derive(CustomDebug)]
+struct Handle<T> {
+    id: u32,
+    #[debug(with = opaque)]
+    inner: T,
+}
+
+struct NotDebug;
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    assert_debug::<Handle<NotDebug>>();
+
+    let packet = Packet {
+        payload: vec![0xde, 0xad],
+        ttl: Duration(30),
+    };
+    assert_eq!(format!("{:?}", packet), "Packet { payload: dead, ttl: 30s }");
+    assert_eq!(format!("{:?}", Message::Raw(vec![1, 255])), "Raw(01ff)");
+
+    let handle = Handle {
+        id: 1,
+        inner: NotDebug,
+    };
+    assert_eq!(format!("{:?}", handle), "Handle { id: 1, inner: .. }");
+}
@@ -0,0 +1,70 @@
+// The string in #[debug(bound = "...")] is parsed as where predicates, so it
+// can hold anything a where clause can: generic arguments, associated type
+// bindings, several `+` bounds, lifetimes, `for<'a>` bounds, and several
+// predicates separated by commas.
+//
+// Bounds on the type replace the inferred bounds but are added to the where
+// clause written on the type and to the bounds given on fields. On a field, #[debug(bound = "...")] only
+// replaces the bounds inferred from that field, other fields still get their
+// bounds inferred.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T: IntoIterator<Item = u8> + Debug, for<'a> &'a U: Debug, 'b: 'static")]
+pub struct Multi<'b, T, U>
+where
+    T: Clone,
+{
+    iter: T,
+    other: &'b U,
+}
+
+#[derive(CustomDebug)]
+pub struct Wrapper<T: Trait, U> {
+    #[debug(bound = "T::Value: Debug")]
+    field: Field<T>,
+    normal: U,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T::Value: Debug")]
+pub struct Field<T: Trait> {
+    values: Vec<T::Value>,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T: Debug")]
+pub struct Mixed<T, U: Trait> {
+    a: T,
+    #[debug(bound = "U::Value: Debug")]
+    b: Vec<U::Value>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Multi<'static, Vec<u8>, String>>();
+    assert_debug::<Wrapper<Id, String>>();
+    assert_debug::<Mixed<u8, Id>>();
+
+    let wrapper = Wrapper::<Id, i32> {
+        field: Field { values: vec![1] },
+        normal: 2,
+    };
+    assert_eq!(
+        format!("{:?}", wrapper),
+        "Wrapper { field: Field { values: [1] }, normal: 2 }",
+    );
+}
@@ -0,0 +1,15 @@
+// #[debug(bound = "...")] on a field only replaces the bounds inferred from
+// the field type. Skipped, redacted and `with` fields are not formatted with
+// their Debug impl, so they get no inferred bounds and a bound on them would
+// be silently ignored. It is reported instead.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Login<T> {
+    username: String,
+    #[debug(redact, bound = "T: std::fmt::Debug")]
+    password: T,
+}
+
+fn main() {}
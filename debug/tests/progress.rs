@@ -9,4 +9,11 @@ fn tests() {
     t.pass("tests/06-bound-trouble.rs");
     t.pass("tests/07-associated-type.rs");
     t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-enum.rs");
+    t.pass("tests/10-tuple-and-unit-struct.rs");
+    t.pass("tests/11-skip-rename-redact.rs");
+    t.compile_fail("tests/12-unknown-attribute.rs");
+    t.pass("tests/13-debug-with.rs");
+    t.pass("tests/14-bound-parsing.rs");
+    t.compile_fail("tests/15-bound-unformatted-field.rs");
 }